@@ -1,18 +1,61 @@
+use crate::cli::{apply_cli_args, CliArgs};
+use crate::keybindings::{load_keybindings, Action, KeybindingsState};
 use crate::navigator::{navigator, Screen};
+use crate::screens::find_in_files::search_job::{FileResult, SearchJob};
+use crate::screens::notepad::commands::poll_print_jobs;
+use crate::screens::notepad::encoding::TextEncoding;
+use crate::screens::notepad::file_watch::{check_external_changes_if_due, DiskState, ExternalChange};
+use crate::screens::notepad::highlighter::HighlightCache;
+use crate::screens::notepad::status_bar::StatusCounts;
+use crate::screens::notepad::history::{Change, History, LineEndings};
+use crate::screens::notepad::line_ending::LineEnding;
+use crate::screens::notepad::markdown_preview::MarkdownPreview;
+use crate::screens::notepad::pdf_export::PaperSize;
+use crate::screens::notepad::search::SearchOptions;
+use crate::screens::notepad::syntax::LanguageMode;
+use crate::recent_files::load_recent_files;
+use crate::recovery::{
+    autosave, autosave_if_due, load_recovered_documents, show_recovery_dialog, RecoveredDocument,
+    RecoveryInstance,
+};
+use crate::screens::error_dialog::show_error_dialog;
+use crate::session::{discard_session_recovery, restore_session, save_session};
 use crate::shortcuts::shortcuts;
+#[cfg(unix)]
+use crate::single_instance::{socket_path, InstanceServer};
 use eframe::egui;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
+use std::ops::Range;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::Receiver;
 use std::sync::Arc;
 
 pub struct AppState {
     pub screen: Screen,
     pub notepad_state: NotepadState,
     pub settings_state: SettingsState,
+    pub keybindings_state: KeybindingsState,
+    pub find_in_files_state: FindInFilesState,
     pub strings: HashMap<String, String>,
+    /// Error shown to the user in a dialog until dismissed.
+    pub error_message: Option<String>,
+    /// Time of the last autosave, in seconds since the app started.
+    pub last_autosave: f64,
+    pub recovered_documents: Vec<RecoveredDocument>,
+    pub recovery_instance: Option<RecoveryInstance>,
+    /// Most recently opened files, newest first.
+    pub recent_files: Vec<PathBuf>,
+    /// Set by `--wait`: the window closes once all of these documents are closed.
+    pub wait_for_documents: Option<Vec<u64>>,
+    /// System clipboard for rich copies, opened on first use and kept open so that what was
+    /// copied stays available (on X11 it is served by the owner until it exits).
+    pub clipboard: Option<arboard::Clipboard>,
+    #[cfg(unix)]
+    pub instance_server: Option<InstanceServer>,
 
     config_dir: String,
     config_file: String,
@@ -24,7 +67,18 @@ impl Default for AppState {
             screen: Screen::Notepad,
             notepad_state: NotepadState::default(),
             settings_state: SettingsState::default(),
+            keybindings_state: KeybindingsState::default(),
+            find_in_files_state: FindInFilesState::default(),
             strings: HashMap::new(),
+            error_message: None,
+            last_autosave: 0.0,
+            recovered_documents: Vec::new(),
+            recovery_instance: None,
+            recent_files: Vec::new(),
+            wait_for_documents: None,
+            clipboard: None,
+            #[cfg(unix)]
+            instance_server: None,
             config_dir: "NotepadR".to_string(),
             config_file: "config.json".to_string(),
         }
@@ -37,26 +91,54 @@ impl eframe::App for AppState {
             self.apply_theme(ctx);
             self.apply_font_settings(ctx);
 
+            // Shortcuts run before the screens so they can consume keys the text editor would handle.
+            shortcuts(ctx, self);
+
             navigator(self, ctx, frame);
 
-            if self.settings_state.current.confirm_on_close {
-                if ctx.input(|i| i.viewport().close_requested()) {
-                    if self.has_unsaved_changes() {
-                        ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
-                        self.notepad_state.pending_action = PendingAction::CloseApp;
-                        self.notepad_state.show_save_modal = true;
-                    }
-                }
+            show_error_dialog(ctx, self);
+            show_recovery_dialog(ctx, self);
+
+            if self.settings_state.current.confirm_on_close
+                && ctx.input(|i| i.viewport().close_requested())
+                && let Some(index) = self
+                    .notepad_state
+                    .first_document_blocking_close(self.settings_state.current.restore_untitled_content, &[])
+            {
+                ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+                self.notepad_state.active_document = index;
+                self.notepad_state.pending_action = PendingAction::CloseApp { discarded: Vec::new() };
+                self.notepad_state.show_save_modal = true;
             }
 
-            shortcuts(ctx, self);
+            autosave_if_due(self, ctx);
+            check_external_changes_if_due(self, ctx);
+            poll_print_jobs(self, ctx);
+            self.close_if_wait_finished(ctx);
+            #[cfg(unix)]
+            self.open_forwarded_files(ctx);
         });
     }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        // Dirty buffers left open (e.g. with close confirmation disabled) stay recoverable.
+        if self.settings_state.current.autosave_enabled {
+            autosave(self);
+        }
+
+        // A `--wait` window is a one-off edit and must not replace the regular session.
+        if self.wait_for_documents.is_none() {
+            save_session(self);
+            discard_session_recovery(self);
+        }
+    }
 }
 
 impl AppState {
-    pub fn new(cc: &eframe::CreationContext) -> Self {
+    pub fn new(cc: &eframe::CreationContext, cli_args: CliArgs) -> Self {
         setup_custom_fonts(&cc.egui_ctx);
+        // Ctrl+Plus/Minus/0 zoom the editor text instead of the whole window.
+        cc.egui_ctx.options_mut(|options| options.zoom_with_keyboard = false);
 
         let mut app = Self::default();
 
@@ -68,19 +150,78 @@ impl AppState {
             println!("ERROR: loading translations -> {}", e);
         }
 
+        app.keybindings_state.current = load_keybindings(&app);
+        app.keybindings_state.unsaved = app.keybindings_state.current.clone();
+
+        app.notepad_state.documents = vec![Document::with_settings(&app.settings_state.current)];
+        #[cfg(unix)]
+        if !cli_args.wait && app.settings_state.current.single_instance {
+            let ctx = cc.egui_ctx.clone();
+            match socket_path().and_then(|path| InstanceServer::start(path, move || ctx.request_repaint())) {
+                Ok(server) => app.instance_server = Some(server),
+                Err(e) => log::error!("ERROR: starting single-instance listener -> {}", e),
+            }
+        }
+
+        if !cli_args.wait {
+            restore_session(&mut app);
+        }
+        apply_cli_args(&mut app, cli_args);
+        app.recovered_documents = load_recovered_documents(&app);
+        app.recent_files = load_recent_files(&app);
+
         app
     }
 
-    pub fn has_unsaved_changes(&self) -> bool {
-        match &self.notepad_state.file_content {
-            Some(content) => self.notepad_state.current_content != *content,
-            None => !self.notepad_state.current_content.is_empty()
+    /// Reads `single_instance` before the window exists, to decide whether to forward files.
+    pub fn single_instance_enabled() -> bool {
+        let mut app = Self::default();
+        app.load_settings_from_disk().is_ok() && app.settings_state.current.single_instance
+    }
+
+    /// Opens files sent by another invocation and brings the window to the front.
+    #[cfg(unix)]
+    fn open_forwarded_files(&mut self, ctx: &egui::Context) {
+        let Some(server) = &self.instance_server else {
+            return;
+        };
+
+        let forwarded: Vec<CliArgs> = std::iter::from_fn(|| server.try_recv()).collect();
+        if forwarded.is_empty() {
+            return;
         }
+
+        for cli_args in forwarded {
+            apply_cli_args(self, cli_args);
+        }
+
+        ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+        ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
     }
 
-    pub fn save_settings_to_disk(&self) -> Result<(), Box<dyn std::error::Error>> {
+    /// With `--wait`, closes the window once the documents it was opened for are closed. If
+    /// none of them could be opened, the window stays up with the error until closed by hand.
+    fn close_if_wait_finished(&self, ctx: &egui::Context) {
+        if let Some(ids) = &self.wait_for_documents
+            && !ids.is_empty()
+            && !self.notepad_state.documents.iter().any(|document| ids.contains(&document.id))
+        {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+    }
+
+    pub fn report_error(&mut self, message: String) {
+        log::error!("ERROR: {}", message);
+        self.error_message = Some(message);
+    }
+
+    pub fn app_config_dir(&self) -> Result<PathBuf, Box<dyn std::error::Error>> {
         let config_dir = dirs::config_dir().ok_or("Config folder not found")?;
-        let app_config_dir = config_dir.join(&self.config_dir);
+        Ok(config_dir.join(&self.config_dir))
+    }
+
+    pub fn save_settings_to_disk(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let app_config_dir = self.app_config_dir()?;
         std::fs::create_dir_all(&app_config_dir)?;
         let config_path = app_config_dir.join(&self.config_file);
 
@@ -91,8 +232,7 @@ impl AppState {
     }
 
     pub fn load_settings_from_disk(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        let config_dir = dirs::config_dir().ok_or("Config folder not found")?;
-        let config_path = config_dir.join(&self.config_dir).join(&self.config_file);
+        let config_path = self.app_config_dir()?.join(&self.config_file);
 
         if config_path.exists() {
             let json = std::fs::read_to_string(config_path)?;
@@ -164,46 +304,327 @@ impl AppState {
 
 pub enum PendingAction {
     None,
-    NewFile,
-    OpenFile,
-    CloseApp,
+    CloseTab(usize),
+    /// Walking the dirty tabs before closing; `discarded` holds the ids of those whose
+    /// changes the user chose to drop, closed only once every tab was answered.
+    CloseApp { discarded: Vec<u64> },
 }
 
-pub struct NotepadState {
+pub struct Document {
+    pub id: u64,
     pub current_content: String,
+    /// Bumped on every change to `current_content`, so caches can skip comparing the text.
+    pub revision: u64,
     pub current_file_path: Option<PathBuf>,
     pub file_content: Option<String>,
-    pub show_save_modal: bool,
-    pub pending_action: PendingAction,
+    pub encoding: TextEncoding,
+    pub line_ending: LineEnding,
+    /// Line ending of the file on disk, so converting marks the document as modified.
+    pub file_line_ending: Option<LineEnding>,
+    pub mixed_line_endings: bool,
+    /// Set when mixed line endings were converted, which changes the file even if the
+    /// chosen style is the one it mostly used.
+    pub line_endings_unified: bool,
+    pub read_only: bool,
+    pub language_mode: LanguageMode,
+    pub highlight_cache: HighlightCache,
+    /// Rendered Markdown shown beside the editor while `show_preview` is set.
+    pub show_preview: bool,
+    pub preview: MarkdownPreview,
+    pub disk_state: Option<DiskState>,
+    /// Hash of the content last written to the recovery folder, if any.
+    pub autosave_hash: Option<u64>,
+    pub history: History,
+    pub cursor: Option<egui::text::CCursorRange>,
+    /// Char range to select in the editor on the next frame, scrolled into view.
+    pub pending_selection: Option<Range<usize>>,
+    pub scroll_offset: egui::Vec2,
+    /// Scroll position to apply on the next frame instead of scrolling to the selection.
+    pub pending_scroll_offset: Option<egui::Vec2>,
 }
 
-impl Default for NotepadState {
+impl Default for Document {
     fn default() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
         Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
             current_content: String::new(),
+            revision: 0,
             current_file_path: None,
             file_content: None,
+            encoding: TextEncoding::default(),
+            line_ending: LineEnding::default(),
+            file_line_ending: None,
+            mixed_line_endings: false,
+            line_endings_unified: false,
+            read_only: false,
+            language_mode: LanguageMode::default(),
+            highlight_cache: HighlightCache::default(),
+            show_preview: false,
+            preview: MarkdownPreview::default(),
+            disk_state: None,
+            autosave_hash: None,
+            history: History::default(),
+            cursor: None,
+            pending_selection: None,
+            scroll_offset: egui::Vec2::ZERO,
+            pending_scroll_offset: None,
+        }
+    }
+}
+
+impl Document {
+    /// An empty document using the defaults for new files.
+    pub fn with_settings(settings: &Settings) -> Self {
+        Self {
+            encoding: TextEncoding::from_name(&settings.default_encoding),
+            line_ending: settings.default_line_ending,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        let content_changed = match &self.file_content {
+            Some(content) => self.current_content != *content,
+            None => !self.current_content.is_empty()
+        };
+
+        content_changed
+            || self.line_endings_unified
+            || self.file_line_ending.is_some_and(|ending| ending != self.line_ending)
+    }
+
+    pub fn detect_language_mode(&mut self) {
+        self.language_mode = LanguageMode::detect(self.current_file_path.as_deref(), &self.current_content);
+    }
+
+    /// An untitled, empty document that can be replaced by an opened file.
+    pub fn is_pristine(&self) -> bool {
+        self.current_file_path.is_none() && self.current_content.is_empty()
+    }
+
+    /// Replaces the whole content as a single undoable step.
+    pub fn replace_content(&mut self, content: String, undo_limit: usize) {
+        self.history.record(&self.current_content, &content, undo_limit);
+        self.set_content(content);
+    }
+
+    pub fn set_content(&mut self, content: String) {
+        self.current_content = content;
+        self.revision += 1;
+    }
+
+    pub fn undo(&mut self, undo_limit: usize) {
+        if let Some(change) = self.history.undo(&mut self.current_content, undo_limit) {
+            self.apply_change(change);
+        }
+    }
+
+    pub fn redo(&mut self, undo_limit: usize) {
+        if let Some(change) = self.history.redo(&mut self.current_content, undo_limit) {
+            self.apply_change(change);
+        }
+    }
+
+    fn apply_change(&mut self, change: Change) {
+        match change {
+            Change::Text { cursor } => {
+                self.revision += 1;
+                self.pending_selection = Some(cursor..cursor);
+            }
+            Change::LineEndings(line_endings) => self.set_line_endings(line_endings),
+        }
+    }
+
+    pub fn line_endings(&self) -> LineEndings {
+        LineEndings {
+            line_ending: self.line_ending,
+            mixed: self.mixed_line_endings,
+            unified: self.line_endings_unified,
+        }
+    }
+
+    pub fn set_line_endings(&mut self, line_endings: LineEndings) {
+        self.line_ending = line_endings.line_ending;
+        self.mixed_line_endings = line_endings.mixed;
+        self.line_endings_unified = line_endings.unified;
+    }
+
+    /// Currently selected char range, ordered from start to end.
+    pub fn selection(&self) -> Range<usize> {
+        self.cursor
+            .map_or(0..0, |range| range.as_sorted_char_range())
+    }
+
+    pub fn title(&self, untitled: &str) -> String {
+        self.current_file_path
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| untitled.to_string())
+    }
+}
+
+pub enum EncodingDialogMode {
+    Reopen,
+    Save,
+}
+
+pub struct EncodingDialog {
+    pub mode: EncodingDialogMode,
+    pub encoding: TextEncoding,
+    pub error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct CommandPalette {
+    pub query: String,
+    /// Index into the filtered command list.
+    pub selected: usize,
+    pub focus_query: bool,
+}
+
+#[derive(Default)]
+pub struct GoToLineDialog {
+    pub input: String,
+    pub error: Option<String>,
+    pub focus_input: bool,
+}
+
+pub struct ExternalChangePrompt {
+    pub document_id: u64,
+    pub change: ExternalChange,
+    /// Encoding of a save that raised the prompt, finished if the user keeps their version.
+    pub pending_save: Option<TextEncoding>,
+}
+
+#[derive(Default)]
+pub struct FindReplaceState {
+    pub visible: bool,
+    pub show_replace: bool,
+    pub focus_query: bool,
+    pub query: String,
+    pub replacement: String,
+    pub options: SearchOptions,
+    /// Byte ranges of the matches in the active document.
+    pub matches: Vec<Range<usize>>,
+    pub current_match: Option<usize>,
+    pub error: Option<String>,
+    /// What `matches` were found for, so the search only re-runs when it changes.
+    pub search_key: Option<SearchKey>,
+}
+
+#[derive(PartialEq)]
+pub struct SearchKey {
+    pub document_id: u64,
+    pub revision: u64,
+    pub query: String,
+    pub options: SearchOptions,
+}
+
+pub struct NotepadState {
+    pub documents: Vec<Document>,
+    pub active_document: usize,
+    pub show_save_modal: bool,
+    pub show_revert_modal: bool,
+    pub pending_action: PendingAction,
+    pub find_replace: FindReplaceState,
+    pub encoding_dialog: Option<EncodingDialog>,
+    pub go_to_line_dialog: Option<GoToLineDialog>,
+    pub command_palette: Option<CommandPalette>,
+    /// Commands run from the palette, most recent first.
+    pub recent_actions: Vec<Action>,
+    pub external_change_prompt: Option<ExternalChangePrompt>,
+    /// Time of the last check for files changed on disk, in seconds since the app started.
+    pub last_external_check: f64,
+    /// Editor text scale on top of `Settings::font_size`; kept in the session, not the config.
+    pub zoom: f32,
+    /// Print jobs handed to the system spooler on background threads.
+    pub print_jobs: Vec<Receiver<Result<(), String>>>,
+    pub status_counts: StatusCounts,
+}
+
+impl Default for NotepadState {
+    fn default() -> Self {
+        Self {
+            documents: vec![Document::default()],
+            active_document: 0,
             show_save_modal: false,
+            show_revert_modal: false,
             pending_action: PendingAction::None,
+            find_replace: FindReplaceState::default(),
+            encoding_dialog: None,
+            go_to_line_dialog: None,
+            command_palette: None,
+            recent_actions: Vec::new(),
+            external_change_prompt: None,
+            last_external_check: 0.0,
+            zoom: 1.0,
+            print_jobs: Vec::new(),
+            status_counts: StatusCounts::default(),
         }
     }
 }
 
-pub struct SettingsState {
-    pub current: Settings,
-    pub unsaved: Settings,
+impl NotepadState {
+    pub fn active(&self) -> &Document {
+        &self.documents[self.active_document]
+    }
+
+    pub fn active_mut(&mut self) -> &mut Document {
+        &mut self.documents[self.active_document]
+    }
+
+    /// First document that has to be saved or discarded before the app closes, other than
+    /// the `discarded` ones. Untitled documents are skipped when the session keeps their content.
+    pub fn first_document_blocking_close(&self, keep_untitled: bool, discarded: &[u64]) -> Option<usize> {
+        self.documents.iter().position(|document| {
+            document.is_dirty()
+                && !(keep_untitled && document.current_file_path.is_none())
+                && !discarded.contains(&document.id)
+        })
+    }
+}
+
+pub struct FindInFilesState {
+    pub folder: String,
+    pub query: String,
+    pub include: String,
+    pub exclude: String,
+    pub options: SearchOptions,
+    pub results: Vec<FileResult>,
+    pub job: Option<SearchJob>,
+    pub files_scanned: usize,
+    pub cancelled: bool,
+    pub error: Option<String>,
 }
 
-impl Default for SettingsState {
+impl Default for FindInFilesState {
     fn default() -> Self {
         Self {
-            current: Settings::default(),
-            unsaved: Settings::default(),
+            folder: String::new(),
+            query: String::new(),
+            include: String::new(),
+            exclude: ".git, target, node_modules".to_string(),
+            options: SearchOptions::default(),
+            results: Vec::new(),
+            job: None,
+            files_scanned: 0,
+            cancelled: false,
+            error: None,
         }
     }
 }
 
+#[derive(Default)]
+pub struct SettingsState {
+    pub current: Settings,
+    pub unsaved: Settings,
+}
+
 #[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Settings {
     pub dark_mode: bool,
     pub font_name: String,
@@ -211,6 +632,27 @@ pub struct Settings {
     pub default_path: String,
     pub language: Language,
     pub confirm_on_close: bool,
+    pub undo_limit: usize,
+    pub default_encoding: String,
+    pub default_line_ending: LineEnding,
+    /// Number of previous versions kept as `.bak` files when saving; 0 disables backups.
+    pub backup_count: usize,
+    pub autosave_enabled: bool,
+    pub autosave_interval_secs: u64,
+    pub auto_reload_unmodified: bool,
+    pub recent_files_limit: usize,
+    /// Keep the text of untitled documents in the session instead of asking to save it.
+    pub restore_untitled_content: bool,
+    /// Forward files opened from the command line to the window that is already running.
+    pub single_instance: bool,
+    pub show_line_numbers: bool,
+    pub word_wrap: WordWrap,
+    /// Column used by `WordWrap::Column`.
+    pub wrap_column: usize,
+    pub paper_size: PaperSize,
+    pub print_margin_mm: f32,
+    pub print_line_numbers: bool,
+    pub print_header_footer: bool,
 }
 
 impl Default for Settings {
@@ -222,6 +664,23 @@ impl Default for Settings {
             default_path: "".to_string(),
             language: Language::English,
             confirm_on_close: true,
+            undo_limit: 500,
+            default_encoding: "UTF-8".to_string(),
+            default_line_ending: LineEnding::default(),
+            backup_count: 0,
+            autosave_enabled: true,
+            autosave_interval_secs: 30,
+            auto_reload_unmodified: false,
+            recent_files_limit: 10,
+            restore_untitled_content: false,
+            single_instance: false,
+            show_line_numbers: true,
+            word_wrap: WordWrap::Off,
+            wrap_column: 80,
+            paper_size: PaperSize::default(),
+            print_margin_mm: 15.0,
+            print_line_numbers: false,
+            print_header_footer: true,
         }
     }
 }
@@ -245,22 +704,42 @@ pub enum Language {
     French,
 }
 
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum WordWrap {
+    Off,
+    Window,
+    Column,
+}
+
+const ROBOTO_TTF: &[u8] = include_bytes!("../assets/fonts/Roboto-Regular.ttf");
+const INTER_TTF: &[u8] = include_bytes!("../assets/fonts/Inter_18pt-Regular.ttf");
+const FIRA_CODE_TTF: &[u8] = include_bytes!("../assets/fonts/FiraCode-Regular.ttf");
+
+/// Bundled font file for `Settings::font_name`; Roboto stands in for egui's default font.
+pub fn font_data(font_name: &str) -> &'static [u8] {
+    match font_name {
+        "Inter" => INTER_TTF,
+        "Fira Code" => FIRA_CODE_TTF,
+        _ => ROBOTO_TTF,
+    }
+}
+
 fn setup_custom_fonts(ctx: &egui::Context) {
     let mut fonts = egui::FontDefinitions::default();
 
     fonts.font_data.insert(
         "Roboto".to_owned(),
-        Arc::from(egui::FontData::from_static(include_bytes!("../assets/fonts/Roboto-Regular.ttf"))),
+        Arc::from(egui::FontData::from_static(ROBOTO_TTF)),
     );
 
     fonts.font_data.insert(
         "Inter".to_owned(),
-        Arc::from(egui::FontData::from_static(include_bytes!("../assets/fonts/Inter_18pt-Regular.ttf"))),
+        Arc::from(egui::FontData::from_static(INTER_TTF)),
     );
 
     fonts.font_data.insert(
         "Fira Code".to_owned(),
-        Arc::from(egui::FontData::from_static(include_bytes!("../assets/fonts/FiraCode-Regular.ttf"))),
+        Arc::from(egui::FontData::from_static(FIRA_CODE_TTF)),
     );
 
     fonts.families.insert(
@@ -0,0 +1,269 @@
+use crate::app_state::AppState;
+use crate::navigator::Screen;
+use crate::screens::notepad::commands::{go_to_position, new_file, open_path, report_open_error};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+const USAGE: &str = "\
+Usage: Notepad [OPTIONS] [FILE[:LINE[:COLUMN]]]...
+
+Options:
+  --new        Open an empty untitled document
+  --readonly   Open the given files without allowing edits
+  --wait       Close the window once the opened documents are closed
+               (needs a file or --new)
+  -h, --help   Show this help
+  --           Treat the remaining arguments as file names";
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct CliArgs {
+    pub files: Vec<FileArg>,
+    pub new_document: bool,
+    pub readonly: bool,
+    pub wait: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FileArg {
+    pub path: PathBuf,
+    /// 1-based line and column to place the cursor at.
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+pub enum CliError {
+    Help,
+    Invalid(String),
+}
+
+impl CliError {
+    pub fn exit(self) -> ! {
+        match self {
+            CliError::Help => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            CliError::Invalid(message) => {
+                eprintln!("Notepad: {}\nTry 'Notepad --help' for more information.", message);
+                std::process::exit(2);
+            }
+        }
+    }
+}
+
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<CliArgs, CliError> {
+    let mut cli_args = CliArgs::default();
+    let mut only_files = false;
+
+    for arg in args {
+        if !only_files && arg.starts_with('-') && arg.len() > 1 {
+            match arg.as_str() {
+                "--new" => cli_args.new_document = true,
+                "--readonly" => cli_args.readonly = true,
+                "--wait" => cli_args.wait = true,
+                "-h" | "--help" => return Err(CliError::Help),
+                "--" => only_files = true,
+                _ => return Err(CliError::Invalid(format!("unknown option '{}'", arg))),
+            }
+        } else {
+            cli_args.files.push(parse_file_arg(&arg)?);
+        }
+    }
+
+    // Nothing would ever be closed, so the caller would wait forever.
+    if cli_args.wait && cli_args.files.is_empty() && !cli_args.new_document {
+        return Err(CliError::Invalid("--wait needs a file or --new".to_string()));
+    }
+
+    Ok(cli_args)
+}
+
+/// Splits `path:line:col`, unless a file with the full name exists.
+fn parse_file_arg(arg: &str) -> Result<FileArg, CliError> {
+    let literal = Path::new(arg);
+    let (path, line, column) = if literal.exists() {
+        (literal.to_path_buf(), None, None)
+    } else {
+        split_position(arg)
+    };
+
+    validate_path(&path)?;
+    let path = std::path::absolute(&path).unwrap_or(path);
+    Ok(FileArg { path, line, column })
+}
+
+fn split_position(arg: &str) -> (PathBuf, Option<usize>, Option<usize>) {
+    let mut parts = arg.rsplitn(3, ':');
+    let last = parts.next().and_then(|part| part.parse::<usize>().ok());
+    let middle = parts.next();
+    let rest = parts.next();
+
+    match (last, middle, rest) {
+        (Some(column), Some(line), Some(path)) if line.parse::<usize>().is_ok() && !path.is_empty() => {
+            (PathBuf::from(path), line.parse().ok(), Some(column))
+        }
+        (Some(line), Some(middle), rest) => {
+            let path = match rest {
+                Some(rest) => format!("{}:{}", rest, middle),
+                None => middle.to_string(),
+            };
+            (PathBuf::from(path), Some(line), None)
+        }
+        _ => (PathBuf::from(arg), None, None),
+    }
+}
+
+/// A file that doesn't exist yet is fine as long as its folder does; it is created on save.
+fn validate_path(path: &Path) -> Result<(), CliError> {
+    if path.is_dir() {
+        return Err(CliError::Invalid(format!("'{}' is a folder, not a file", path.display())));
+    }
+
+    if !path.exists() {
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+
+        if !parent.is_dir() {
+            return Err(CliError::Invalid(format!(
+                "cannot open '{}': folder '{}' does not exist",
+                path.display(),
+                parent.display()
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Opens the files given on the command line on top of the restored session.
+pub fn apply_cli_args(state: &mut AppState, cli_args: CliArgs) {
+    let mut opened = Vec::new();
+
+    for file in cli_args.files {
+        let result = if file.path.exists() {
+            open_path(state, file.path.clone())
+        } else {
+            if !state.notepad_state.active().is_pristine() {
+                new_file(state);
+            }
+            let document = state.notepad_state.active_mut();
+            document.current_file_path = Some(file.path.clone());
+            document.detect_language_mode();
+            Ok(())
+        };
+
+        if let Err(e) = result {
+            report_open_error(state, &file.path, &e);
+            continue;
+        }
+
+        if let Some(line) = file.line {
+            go_to_position(state, line, file.column.unwrap_or(1));
+        }
+
+        let document = state.notepad_state.active_mut();
+        document.read_only = cli_args.readonly;
+        opened.push(document.id);
+    }
+
+    if cli_args.new_document {
+        if !state.notepad_state.active().is_pristine() {
+            new_file(state);
+        }
+        opened.push(state.notepad_state.active().id);
+    }
+
+    if !opened.is_empty() {
+        state.screen = Screen::Notepad;
+    }
+
+    if cli_args.wait {
+        state.wait_for_documents = Some(opened);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CliArgs, CliError> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn invalid(args: &[&str]) -> String {
+        match parse(args) {
+            Err(CliError::Invalid(message)) => message,
+            _ => panic!("{:?} should be rejected", args),
+        }
+    }
+
+    #[test]
+    fn options_are_recognised() {
+        let Ok(args) = parse(&["--new", "--readonly"]) else {
+            panic!("valid options were rejected");
+        };
+        assert!(args.new_document && args.readonly && !args.wait);
+        assert!(args.files.is_empty());
+    }
+
+    #[test]
+    fn help_and_unknown_options() {
+        assert!(matches!(parse(&["-h"]), Err(CliError::Help)));
+        assert!(matches!(parse(&["--help"]), Err(CliError::Help)));
+        assert!(invalid(&["--bogus"]).contains("--bogus"));
+    }
+
+    #[test]
+    fn wait_needs_something_to_wait_for() {
+        assert!(invalid(&["--wait"]).contains("--wait"));
+        assert!(parse(&["--wait", "--new"]).is_ok_and(|args| args.wait));
+    }
+
+    #[test]
+    fn file_positions_are_split_off() {
+        assert_eq!(
+            split_position("notes.txt:12:5"),
+            (PathBuf::from("notes.txt"), Some(12), Some(5))
+        );
+        assert_eq!(split_position("notes.txt:12"), (PathBuf::from("notes.txt"), Some(12), None));
+        assert_eq!(split_position("notes.txt"), (PathBuf::from("notes.txt"), None, None));
+        assert_eq!(split_position("C:\\notes.txt:3"), (PathBuf::from("C:\\notes.txt"), Some(3), None));
+        assert_eq!(split_position("a:b:7"), (PathBuf::from("a:b"), Some(7), None));
+    }
+
+    #[test]
+    fn existing_files_keep_their_full_name() {
+        let folder = tempfile::tempdir().unwrap();
+        let path = folder.path().join("odd:1");
+        std::fs::write(&path, "").unwrap();
+
+        let Ok(args) = parse(&[path.to_str().unwrap()]) else {
+            panic!("existing file was rejected");
+        };
+        assert_eq!(args.files[0].path, path);
+        assert_eq!(args.files[0].line, None);
+    }
+
+    #[test]
+    fn double_dash_treats_the_rest_as_files() {
+        let folder = tempfile::tempdir().unwrap();
+        let path = folder.path().join("--new");
+
+        let Ok(args) = parse(&["--", path.to_str().unwrap()]) else {
+            panic!("file after -- was rejected");
+        };
+        assert!(!args.new_document);
+        assert_eq!(args.files[0].path, path);
+    }
+
+    #[test]
+    fn folders_and_missing_parents_are_rejected() {
+        let folder = tempfile::tempdir().unwrap();
+        assert!(invalid(&[folder.path().to_str().unwrap()]).contains("is a folder"));
+
+        let missing = folder.path().join("missing").join("file.txt");
+        assert!(invalid(&[missing.to_str().unwrap()]).contains("does not exist"));
+    }
+}
@@ -0,0 +1,671 @@
+use crate::app_state::AppState;
+use crate::screens::notepad::line_ending::LineEnding;
+use eframe::egui;
+use log::{error, warn};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+const KEYBINDINGS_FILE: &str = "keybindings.json";
+
+/// Commands that can be bound to keys or run from the command palette, stored in
+/// `keybindings.json` by their `id`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Action {
+    NewFile,
+    OpenFile,
+    Save,
+    SaveAs,
+    SaveCopyAs,
+    RevertToSaved,
+    ReopenWithEncoding,
+    SaveWithEncoding,
+    ExportPdf,
+    ExportHtml,
+    Print,
+    CloseTab,
+    Undo,
+    Redo,
+    CopyAsHtml,
+    Find,
+    Replace,
+    FindInFiles,
+    GoToLine,
+    LineEndingsLf,
+    LineEndingsCrLf,
+    LineEndingsCr,
+    ToggleLineNumbers,
+    WordWrapOff,
+    WordWrapWindow,
+    WordWrapColumn,
+    MarkdownPreview,
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
+    CommandPalette,
+    OpenSettings,
+}
+
+impl Action {
+    pub const ALL: [Action; 32] = [
+        Action::NewFile,
+        Action::OpenFile,
+        Action::Save,
+        Action::SaveAs,
+        Action::SaveCopyAs,
+        Action::RevertToSaved,
+        Action::ReopenWithEncoding,
+        Action::SaveWithEncoding,
+        Action::ExportPdf,
+        Action::ExportHtml,
+        Action::Print,
+        Action::CloseTab,
+        Action::Undo,
+        Action::Redo,
+        Action::CopyAsHtml,
+        Action::Find,
+        Action::Replace,
+        Action::FindInFiles,
+        Action::GoToLine,
+        Action::LineEndingsLf,
+        Action::LineEndingsCrLf,
+        Action::LineEndingsCr,
+        Action::ToggleLineNumbers,
+        Action::WordWrapOff,
+        Action::WordWrapWindow,
+        Action::WordWrapColumn,
+        Action::MarkdownPreview,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::ZoomReset,
+        Action::CommandPalette,
+        Action::OpenSettings,
+    ];
+
+    pub fn id(self) -> &'static str {
+        match self {
+            Action::NewFile => "file.new",
+            Action::OpenFile => "file.open",
+            Action::Save => "file.save",
+            Action::SaveAs => "file.save_as",
+            Action::SaveCopyAs => "file.save_copy_as",
+            Action::RevertToSaved => "file.revert",
+            Action::ReopenWithEncoding => "file.reopen_with_encoding",
+            Action::SaveWithEncoding => "file.save_with_encoding",
+            Action::ExportPdf => "file.export_pdf",
+            Action::ExportHtml => "file.export_html",
+            Action::Print => "file.print",
+            Action::CloseTab => "file.close",
+            Action::Undo => "edit.undo",
+            Action::Redo => "edit.redo",
+            Action::CopyAsHtml => "edit.copy_as_html",
+            Action::Find => "edit.find",
+            Action::Replace => "edit.replace",
+            Action::FindInFiles => "edit.find_in_files",
+            Action::GoToLine => "edit.go_to_line",
+            Action::LineEndingsLf => "edit.line_endings_lf",
+            Action::LineEndingsCrLf => "edit.line_endings_crlf",
+            Action::LineEndingsCr => "edit.line_endings_cr",
+            Action::ToggleLineNumbers => "view.line_numbers",
+            Action::WordWrapOff => "view.word_wrap_off",
+            Action::WordWrapWindow => "view.word_wrap_window",
+            Action::WordWrapColumn => "view.word_wrap_column",
+            Action::MarkdownPreview => "view.markdown_preview",
+            Action::ZoomIn => "view.zoom_in",
+            Action::ZoomOut => "view.zoom_out",
+            Action::ZoomReset => "view.zoom_reset",
+            Action::CommandPalette => "app.command_palette",
+            Action::OpenSettings => "app.settings",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.id() == id)
+    }
+
+    /// Localized name, built from the menu entries that run the same command.
+    pub fn label(self, state: &AppState) -> String {
+        let text = |key: &str| state.text(key).trim_end_matches("...").to_string();
+        let submenu = |menu: &str, entry: &str| format!("{}: {}", text(menu), entry);
+
+        match self {
+            Action::NewFile => text("notepad.menu.file.new"),
+            Action::OpenFile => text("notepad.menu.file.open"),
+            Action::Save => text("notepad.menu.file.save"),
+            Action::SaveAs => text("notepad.menu.file.save.as"),
+            Action::SaveCopyAs => text("notepad.menu.file.save.copy"),
+            Action::RevertToSaved => text("notepad.menu.file.revert"),
+            Action::ReopenWithEncoding => text("notepad.menu.file.reopen.encoding"),
+            Action::SaveWithEncoding => text("notepad.menu.file.save.encoding"),
+            Action::ExportPdf => text("notepad.menu.file.export.pdf"),
+            Action::ExportHtml => text("notepad.menu.file.export.html"),
+            Action::Print => text("notepad.menu.file.print"),
+            Action::CloseTab => text("notepad.menu.file.close"),
+            Action::Undo => text("notepad.menu.edit.undo"),
+            Action::Redo => text("notepad.menu.edit.redo"),
+            Action::CopyAsHtml => text("notepad.menu.edit.copy.html"),
+            Action::Find => text("notepad.menu.edit.find"),
+            Action::Replace => text("notepad.menu.edit.replace"),
+            Action::FindInFiles => text("notepad.menu.edit.find.in.files"),
+            Action::GoToLine => text("notepad.menu.edit.go.to.line"),
+            Action::LineEndingsLf => submenu("notepad.menu.edit.line.endings", LineEnding::Lf.label()),
+            Action::LineEndingsCrLf => submenu("notepad.menu.edit.line.endings", LineEnding::CrLf.label()),
+            Action::LineEndingsCr => submenu("notepad.menu.edit.line.endings", LineEnding::Cr.label()),
+            Action::ToggleLineNumbers => text("notepad.menu.view.line.numbers"),
+            Action::WordWrapOff => submenu("notepad.menu.view.word.wrap", &text("notepad.menu.view.word.wrap.off")),
+            Action::WordWrapWindow => {
+                submenu("notepad.menu.view.word.wrap", &text("notepad.menu.view.word.wrap.window"))
+            }
+            Action::WordWrapColumn => {
+                submenu("notepad.menu.view.word.wrap", &text("notepad.menu.view.word.wrap.column"))
+            }
+            Action::MarkdownPreview => text("notepad.menu.view.markdown.preview"),
+            Action::ZoomIn => text("notepad.menu.view.zoom.in"),
+            Action::ZoomOut => text("notepad.menu.view.zoom.out"),
+            Action::ZoomReset => text("notepad.menu.view.zoom.reset"),
+            Action::CommandPalette => text("notepad.menu.view.command.palette"),
+            Action::OpenSettings => text("notepad.menu.settings"),
+        }
+    }
+}
+
+/// One key press with the modifiers held, e.g. `Ctrl+Shift+S`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyChord {
+    pub key: egui::Key,
+    pub ctrl: bool,
+    pub cmd: bool,
+    pub alt: bool,
+    pub shift: bool,
+}
+
+/// Chords pressed one after the other, e.g. `Ctrl+K Ctrl+C`.
+pub type KeySequence = Vec<KeyChord>;
+
+impl KeyChord {
+    fn new(key: egui::Key) -> Self {
+        Self {
+            key,
+            ctrl: false,
+            cmd: false,
+            alt: false,
+            shift: false,
+        }
+    }
+
+    /// Cmd on macOS, Ctrl everywhere else.
+    fn primary(key: egui::Key) -> Self {
+        if cfg!(target_os = "macos") {
+            Self { cmd: true, ..Self::new(key) }
+        } else {
+            Self { ctrl: true, ..Self::new(key) }
+        }
+    }
+
+    fn with_shift(self) -> Self {
+        Self { shift: true, ..self }
+    }
+
+    fn from_event(event: &egui::Event) -> Option<(Self, bool)> {
+        match event {
+            egui::Event::Key { key, pressed: true, repeat, modifiers, .. } => Some((
+                Self {
+                    key: *key,
+                    ctrl: modifiers.ctrl,
+                    cmd: modifiers.mac_cmd,
+                    alt: modifiers.alt,
+                    shift: modifiers.shift,
+                },
+                *repeat,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Symbols like `+` need Shift on some layouts, so an unshifted binding accepts either.
+    fn matches(&self, pressed: &KeyChord) -> bool {
+        let shift_matches = self.shift == pressed.shift || (!self.shift && is_symbol(self.key));
+        self.key == pressed.key
+            && self.ctrl == pressed.ctrl
+            && self.cmd == pressed.cmd
+            && self.alt == pressed.alt
+            && shift_matches
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let mut chord = Self::new(egui::Key::Escape);
+        let mut rest = text.trim();
+        loop {
+            if let Some(stripped) = rest.strip_prefix("Ctrl+") {
+                chord.ctrl = true;
+                rest = stripped;
+            } else if let Some(stripped) = rest.strip_prefix("Cmd+") {
+                chord.cmd = true;
+                rest = stripped;
+            } else if let Some(stripped) = rest.strip_prefix("Alt+") {
+                chord.alt = true;
+                rest = stripped;
+            } else if let Some(stripped) = rest.strip_prefix("Shift+") {
+                chord.shift = true;
+                rest = stripped;
+            } else {
+                break;
+            }
+        }
+        chord.key = egui::Key::from_name(rest)?;
+        Some(chord)
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (held, name) in [(self.ctrl, "Ctrl+"), (self.cmd, "Cmd+"), (self.alt, "Alt+"), (self.shift, "Shift+")] {
+            if held {
+                f.write_str(name)?;
+            }
+        }
+        f.write_str(self.key.name())
+    }
+}
+
+fn is_symbol(key: egui::Key) -> bool {
+    use egui::Key::*;
+    matches!(
+        key,
+        Colon
+            | Comma
+            | Backslash
+            | Slash
+            | Pipe
+            | Questionmark
+            | Exclamationmark
+            | OpenBracket
+            | CloseBracket
+            | OpenCurlyBracket
+            | CloseCurlyBracket
+            | Backtick
+            | Minus
+            | Period
+            | Plus
+            | Equals
+            | Semicolon
+            | Quote
+    )
+}
+
+pub fn format_sequence(sequence: &[KeyChord]) -> String {
+    sequence.iter().map(KeyChord::to_string).collect::<Vec<_>>().join(" ")
+}
+
+fn parse_sequence(text: &str) -> Option<KeySequence> {
+    let sequence: Option<KeySequence> = text.split_whitespace().map(KeyChord::parse).collect();
+    sequence.filter(|sequence| !sequence.is_empty())
+}
+
+fn sequence_matches(binding: &[KeyChord], pressed: &[KeyChord]) -> bool {
+    binding.len() == pressed.len() && binding.iter().zip(pressed).all(|(bound, chord)| bound.matches(chord))
+}
+
+fn sequence_starts_with(binding: &[KeyChord], pressed: &[KeyChord]) -> bool {
+    binding.len() > pressed.len() && sequence_matches(&binding[..pressed.len()], pressed)
+}
+
+enum Lookup {
+    Action(Action),
+    Prefix,
+    None,
+}
+
+/// Key sequences bound to each action.
+#[derive(Clone, PartialEq)]
+pub struct Keybindings {
+    pub bindings: BTreeMap<Action, Vec<KeySequence>>,
+}
+
+impl Default for Keybindings {
+    /// The built-in profile: the usual shortcuts on Cmd for macOS and Ctrl elsewhere.
+    fn default() -> Self {
+        use egui::Key;
+        let primary = KeyChord::primary;
+        let bindings = [
+            (Action::NewFile, vec![primary(Key::N)]),
+            (Action::OpenFile, vec![primary(Key::O)]),
+            (Action::Save, vec![primary(Key::S)]),
+            (Action::SaveAs, vec![primary(Key::S).with_shift()]),
+            (Action::Print, vec![primary(Key::P)]),
+            (Action::CloseTab, vec![primary(Key::W)]),
+            (Action::Undo, vec![primary(Key::Z)]),
+            (Action::Redo, vec![primary(Key::Z).with_shift(), primary(Key::Y)]),
+            (Action::Find, vec![primary(Key::F)]),
+            (Action::Replace, vec![primary(Key::H)]),
+            (Action::FindInFiles, vec![primary(Key::F).with_shift()]),
+            (Action::GoToLine, vec![primary(Key::G)]),
+            (Action::ZoomIn, vec![primary(Key::Plus), primary(Key::Equals)]),
+            (Action::ZoomOut, vec![primary(Key::Minus)]),
+            (Action::ZoomReset, vec![primary(Key::Num0)]),
+            (Action::CommandPalette, vec![primary(Key::P).with_shift()]),
+            (Action::OpenSettings, vec![primary(Key::Comma)]),
+        ];
+
+        Self {
+            bindings: bindings
+                .into_iter()
+                .map(|(action, chords)| (action, chords.into_iter().map(|chord| vec![chord]).collect()))
+                .collect(),
+        }
+    }
+}
+
+impl Keybindings {
+    pub fn sequences(&self, action: Action) -> &[KeySequence] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Other actions whose sequence equals `sequence` or one of them starts with the other,
+    /// which would make one of the two unreachable.
+    pub fn conflicts(&self, action: Action, sequence: &[KeyChord]) -> Vec<Action> {
+        self.bindings
+            .iter()
+            .filter(|(other, _)| **other != action)
+            .filter(|(_, sequences)| {
+                sequences.iter().any(|other| {
+                    sequence_matches(other, sequence)
+                        || sequence_starts_with(other, sequence)
+                        || sequence_starts_with(sequence, other)
+                })
+            })
+            .map(|(other, _)| *other)
+            .collect()
+    }
+
+    /// Whether any binding conflicts with another, which keeps the settings from being saved.
+    pub fn has_conflicts(&self) -> bool {
+        self.bindings
+            .iter()
+            .any(|(action, sequences)| sequences.iter().any(|sequence| !self.conflicts(*action, sequence).is_empty()))
+    }
+
+    fn lookup(&self, pressed: &[KeyChord]) -> Lookup {
+        let all = || self.bindings.iter().flat_map(|(action, sequences)| sequences.iter().map(move |s| (*action, s)));
+
+        if let Some((action, _)) = all().find(|(_, sequence)| sequence_matches(sequence, pressed)) {
+            Lookup::Action(action)
+        } else if all().any(|(_, sequence)| sequence_starts_with(sequence, pressed)) {
+            Lookup::Prefix
+        } else {
+            Lookup::None
+        }
+    }
+}
+
+/// A new binding being typed in the Keyboard settings.
+pub struct Recording {
+    pub action: Action,
+    pub sequence: KeySequence,
+}
+
+/// Longest sequence the Keyboard settings will record.
+pub const MAX_SEQUENCE_LEN: usize = 2;
+
+/// Time allowed between the chords of a multi-key binding before the first one is forgotten.
+const PENDING_TIMEOUT_SECS: f64 = 1.5;
+
+#[derive(Default)]
+pub struct KeybindingsState {
+    pub current: Keybindings,
+    pub unsaved: Keybindings,
+    /// Chords typed so far of a multi-key binding.
+    pub pending: KeySequence,
+    /// Time the last chord was added to `pending`, in seconds since the app started.
+    pub pending_since: f64,
+    pub recording: Option<Recording>,
+}
+
+/// Removes the key presses that trigger bindings from this frame's input and returns their actions.
+pub fn take_triggered_actions(ctx: &egui::Context, keybindings_state: &mut KeybindingsState) -> Vec<Action> {
+    let KeybindingsState { current, pending, pending_since, .. } = keybindings_state;
+    let mut actions = Vec::new();
+    let mut consumed_plain_key = false;
+
+    let now = ctx.input(|input| input.time);
+    if now - *pending_since > PENDING_TIMEOUT_SECS {
+        pending.clear();
+    }
+
+    ctx.input_mut(|input| {
+        input.events.retain(|event| {
+            let Some((chord, repeat)) = KeyChord::from_event(event) else {
+                return true;
+            };
+            if repeat && !pending.is_empty() {
+                return false;
+            }
+
+            let mut sequence = std::mem::take(pending);
+            let continues_sequence = !sequence.is_empty();
+            sequence.push(chord);
+
+            match current.lookup(&sequence) {
+                Lookup::Action(action) => actions.push(action),
+                Lookup::Prefix => {
+                    *pending = sequence;
+                    *pending_since = now;
+                }
+                // The rest of an unknown multi-key sequence is swallowed, not typed.
+                Lookup::None if continues_sequence => {}
+                Lookup::None => return true,
+            }
+
+            consumed_plain_key |= !chord.ctrl && !chord.cmd && !chord.alt;
+            false
+        });
+
+        // A bound key without Ctrl/Cmd/Alt also arrives as typed text.
+        if consumed_plain_key {
+            input.events.retain(|event| !matches!(event, egui::Event::Text(_)));
+        }
+    });
+
+    actions
+}
+
+/// Feeds this frame's key presses into the binding being recorded.
+///
+/// Escape cancels; the binding is added once `MAX_SEQUENCE_LEN` chords were typed.
+pub fn capture_recording(ctx: &egui::Context, keybindings_state: &mut KeybindingsState) {
+    let Some(recording) = &mut keybindings_state.recording else {
+        return;
+    };
+
+    let mut cancelled = false;
+    ctx.input_mut(|input| {
+        input.events.retain(|event| {
+            let Some((chord, repeat)) = KeyChord::from_event(event) else {
+                return !matches!(event, egui::Event::Text(_));
+            };
+            if chord == KeyChord::new(egui::Key::Escape) {
+                cancelled = true;
+            } else if !repeat && recording.sequence.len() < MAX_SEQUENCE_LEN {
+                recording.sequence.push(chord);
+            }
+            false
+        });
+    });
+
+    if cancelled {
+        keybindings_state.recording = None;
+    } else if recording.sequence.len() >= MAX_SEQUENCE_LEN {
+        finish_recording(keybindings_state);
+    }
+}
+
+/// Adds the recorded sequence to the unsaved bindings of its action.
+pub fn finish_recording(keybindings_state: &mut KeybindingsState) {
+    let Some(recording) = keybindings_state.recording.take() else {
+        return;
+    };
+    if recording.sequence.is_empty() {
+        return;
+    }
+
+    let sequences = keybindings_state.unsaved.bindings.entry(recording.action).or_default();
+    if !sequences.contains(&recording.sequence) {
+        sequences.push(recording.sequence);
+    }
+}
+
+fn keybindings_path(state: &AppState) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(state.app_config_dir()?.join(KEYBINDINGS_FILE))
+}
+
+/// Reads `keybindings.json` on top of the default profile, so actions missing from the
+/// file keep their default keys.
+pub fn load_keybindings(state: &AppState) -> Keybindings {
+    let mut keybindings = Keybindings::default();
+    let Ok(json) = keybindings_path(state).and_then(|path| Ok(fs::read_to_string(path)?)) else {
+        return keybindings;
+    };
+
+    let stored: BTreeMap<String, Vec<String>> = match serde_json::from_str(&json) {
+        Ok(stored) => stored,
+        Err(e) => {
+            error!("ERROR: reading keybindings -> {}", e);
+            return keybindings;
+        }
+    };
+
+    for (id, sequences) in stored {
+        let Some(action) = Action::from_id(&id) else {
+            warn!("Unknown action in keybindings: {}", id);
+            continue;
+        };
+
+        let sequences = sequences
+            .iter()
+            .filter_map(|text| {
+                let sequence = parse_sequence(text);
+                if sequence.is_none() {
+                    warn!("Invalid key sequence for {}: {}", id, text);
+                }
+                sequence
+            })
+            .collect();
+        keybindings.bindings.insert(action, sequences);
+    }
+
+    keybindings
+}
+
+pub fn save_keybindings(state: &AppState) {
+    let stored: BTreeMap<&str, Vec<String>> = Action::ALL
+        .into_iter()
+        .map(|action| {
+            let sequences = state.keybindings_state.current.sequences(action);
+            (action.id(), sequences.iter().map(|sequence| format_sequence(sequence)).collect())
+        })
+        .collect();
+
+    let result = keybindings_path(state).and_then(|path| {
+        fs::create_dir_all(path.parent().unwrap_or(&path))?;
+        fs::write(path, serde_json::to_string_pretty(&stored)?)?;
+        Ok(())
+    });
+
+    if let Err(e) = result {
+        error!("ERROR: saving keybindings -> {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(text: &str) -> KeyChord {
+        KeyChord::parse(text).unwrap()
+    }
+
+    fn press(chord: KeyChord) -> egui::Event {
+        egui::Event::Key {
+            key: chord.key,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers: egui::Modifiers {
+                alt: chord.alt,
+                ctrl: chord.ctrl,
+                shift: chord.shift,
+                mac_cmd: chord.cmd,
+                command: chord.ctrl || chord.cmd,
+            },
+        }
+    }
+
+    /// Runs one frame at `time` with `chords` pressed and returns the actions they triggered.
+    fn frame(ctx: &egui::Context, state: &mut KeybindingsState, time: f64, chords: &[KeyChord]) -> Vec<Action> {
+        let input = egui::RawInput {
+            time: Some(time),
+            events: chords.iter().copied().map(press).collect(),
+            ..Default::default()
+        };
+        let mut actions = Vec::new();
+        let _ = ctx.run(input, |ctx| actions = take_triggered_actions(ctx, state));
+        actions
+    }
+
+    fn with_binding(action: Action, sequence: KeySequence) -> KeybindingsState {
+        let mut current = Keybindings::default();
+        current.bindings.entry(action).or_default().push(sequence);
+        KeybindingsState {
+            current,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn defaults_have_no_conflicts() {
+        assert!(!Keybindings::default().has_conflicts());
+    }
+
+    #[test]
+    fn equal_and_prefix_sequences_conflict() {
+        let mut keybindings = Keybindings::default();
+        let save = keybindings.sequences(Action::Save)[0].clone();
+        let longer = vec![save[0], chord("Ctrl+A")];
+
+        assert_eq!(keybindings.conflicts(Action::GoToLine, &save), [Action::Save]);
+        assert_eq!(keybindings.conflicts(Action::GoToLine, &longer), [Action::Save]);
+        assert!(keybindings.conflicts(Action::GoToLine, &[chord("Ctrl+Alt+F9")]).is_empty());
+
+        keybindings.bindings.entry(Action::GoToLine).or_default().push(longer);
+        assert!(keybindings.has_conflicts());
+    }
+
+    #[test]
+    fn chords_are_parsed_and_formatted() {
+        let sequence = vec![chord("Ctrl+K"), chord("Ctrl+Shift+C")];
+        assert_eq!(format_sequence(&sequence), "Ctrl+K Ctrl+Shift+C");
+        assert!(KeyChord::parse("Ctrl+NotAKey").is_none());
+    }
+
+    #[test]
+    fn multi_key_bindings_trigger_on_the_last_chord() {
+        let ctx = egui::Context::default();
+        let mut state = with_binding(Action::GoToLine, vec![chord("Ctrl+K"), chord("Ctrl+Alt+P")]);
+
+        assert!(frame(&ctx, &mut state, 1.0, &[chord("Ctrl+K")]).is_empty());
+        assert_eq!(state.pending, [chord("Ctrl+K")]);
+        assert_eq!(frame(&ctx, &mut state, 1.5, &[chord("Ctrl+Alt+P")]), [Action::GoToLine]);
+        assert!(state.pending.is_empty());
+    }
+
+    #[test]
+    fn a_started_sequence_is_forgotten_after_a_pause() {
+        let ctx = egui::Context::default();
+        let mut state = with_binding(Action::GoToLine, vec![chord("Ctrl+K"), chord("Ctrl+Alt+P")]);
+
+        frame(&ctx, &mut state, 1.0, &[chord("Ctrl+K")]);
+        assert!(frame(&ctx, &mut state, 1.0 + PENDING_TIMEOUT_SECS + 0.1, &[chord("Ctrl+Alt+P")]).is_empty());
+        assert!(state.pending.is_empty());
+    }
+}
@@ -1,11 +1,32 @@
 mod app_state;
+mod cli;
+mod keybindings;
 mod navigator;
+mod recent_files;
+mod recovery;
 mod shortcuts;
 mod screens;
+mod session;
+#[cfg(unix)]
+mod single_instance;
 
 fn main() -> eframe::Result {
     env_logger::init();
-    
+
+    let cli_args = cli::parse_args(std::env::args().skip(1)).unwrap_or_else(|e| e.exit());
+
+    // `--wait` keeps its own window so the caller can tell when the document is closed.
+    #[cfg(unix)]
+    if !cli_args.wait && app_state::AppState::single_instance_enabled() {
+        let forwarded = single_instance::socket_path()
+            .and_then(|path| single_instance::forward_to_running_instance(&path, cli_args.clone()));
+        match forwarded {
+            Ok(true) => return Ok(()),
+            Ok(false) => {}
+            Err(e) => eprintln!("ERROR: contacting running instance -> {}", e),
+        }
+    }
+
     let options = eframe::NativeOptions {
         viewport: eframe::egui::ViewportBuilder::default().with_inner_size([1280.0, 720.0]),
         ..Default::default()
@@ -17,7 +38,7 @@ fn main() -> eframe::Result {
         Box::new(|creation_context| {
             egui_extras::install_image_loaders(&creation_context.egui_ctx);
 
-            let mut app_state = app_state::AppState::new(creation_context);
+            let mut app_state = app_state::AppState::new(creation_context, cli_args);
 
             if let Err(err) = app_state.load_settings_from_disk() {
                 eprintln!("ERROR: loading app config -> {:?}", err);
@@ -1,16 +1,21 @@
 use crate::app_state::AppState;
+use crate::screens::find_in_files::find_in_files_screen::find_in_files_screen;
 use crate::screens::notepad::notepad_screen::notepad_screen;
 use crate::screens::settings::settings_screen::settings_screen;
 use eframe::egui;
+use serde::{Deserialize, Serialize};
 
 pub fn navigator(state: &mut AppState, ctx: &egui::Context, frame: &mut eframe::Frame) {
     match state.screen {
         Screen::Notepad => notepad_screen(state, ctx, frame),
-        Screen::Settings => settings_screen(state, ctx, frame)
+        Screen::Settings => settings_screen(state, ctx, frame),
+        Screen::FindInFiles => find_in_files_screen(state, ctx, frame),
     };
 }
 
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Screen {
     Notepad,
-    Settings
+    Settings,
+    FindInFiles,
 }
\ No newline at end of file
@@ -0,0 +1,60 @@
+use crate::app_state::AppState;
+use crate::screens::notepad::commands::{open_path, report_open_error};
+use log::error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const RECENT_FILES_FILE: &str = "recent_files.json";
+
+fn recent_files_path(state: &AppState) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(state.app_config_dir()?.join(RECENT_FILES_FILE))
+}
+
+/// Reads the most-recently-used list, dropping files that no longer exist.
+pub fn load_recent_files(state: &AppState) -> Vec<PathBuf> {
+    let Ok(json) = recent_files_path(state).and_then(|path| Ok(fs::read_to_string(path)?)) else {
+        return Vec::new();
+    };
+
+    let recent_files: Vec<PathBuf> = serde_json::from_str(&json).unwrap_or_default();
+    recent_files.into_iter().filter(|path| path.is_file()).collect()
+}
+
+fn save_recent_files(state: &AppState) {
+    let result = recent_files_path(state).and_then(|path| {
+        fs::create_dir_all(path.parent().unwrap_or(&path))?;
+        fs::write(path, serde_json::to_string_pretty(&state.recent_files)?)?;
+        Ok(())
+    });
+
+    if let Err(e) = result {
+        error!("ERROR: saving recent files -> {}", e);
+    }
+}
+
+/// Moves `path` to the top of the list, trimming it to the configured length.
+pub fn add_recent_file(state: &mut AppState, path: &Path) {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+    state.recent_files.retain(|recent| *recent != path);
+    state.recent_files.insert(0, path);
+    state
+        .recent_files
+        .truncate(state.settings_state.current.recent_files_limit);
+
+    save_recent_files(state);
+}
+
+pub fn clear_recent_files(state: &mut AppState) {
+    state.recent_files.clear();
+    save_recent_files(state);
+}
+
+/// Opens a file picked from the list, forgetting it if it can no longer be read.
+pub fn open_recent_file(state: &mut AppState, path: PathBuf) {
+    if let Err(e) = open_path(state, path.clone()) {
+        state.recent_files.retain(|recent| *recent != path);
+        save_recent_files(state);
+        report_open_error(state, &path, &e);
+    }
+}
@@ -0,0 +1,318 @@
+use crate::app_state::{AppState, Document};
+use crate::screens::notepad::atomic_write::write_atomic;
+use crate::screens::notepad::encoding::{decode_with, TextEncoding};
+use crate::screens::notepad::line_ending::{self, LineEnding};
+use eframe::egui;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+const RECOVERY_DIR: &str = "recovery";
+const LOCK_FILE: &str = "instance.lock";
+
+/// Snapshot of an unsaved buffer, written periodically so it survives a crash.
+#[derive(Serialize, Deserialize)]
+pub struct RecoveredDocument {
+    pub file_path: Option<PathBuf>,
+    pub content: String,
+    pub encoding: String,
+    pub bom: bool,
+    pub line_ending: LineEnding,
+    #[serde(skip)]
+    pub recovery_file: PathBuf,
+}
+
+impl RecoveredDocument {
+    pub fn into_document(self) -> Document {
+        let encoding = TextEncoding {
+            bom: self.bom,
+            ..TextEncoding::from_name(&self.encoding)
+        };
+
+        // Compare against what is on disk now so the restored buffer shows as modified.
+        let file_content = self.file_path.as_ref().and_then(|path| {
+            let bytes = fs::read(path).ok()?;
+            let (content, _, _) = decode_with(&bytes, encoding.encoding);
+            Some(line_ending::normalize(&content))
+        });
+
+        let mut document = Document {
+            current_content: self.content,
+            current_file_path: self.file_path,
+            file_content,
+            encoding,
+            line_ending: self.line_ending,
+            file_line_ending: Some(self.line_ending),
+            ..Default::default()
+        };
+        document.detect_language_mode();
+        document
+    }
+}
+
+fn recovery_dir(state: &AppState) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(state.app_config_dir()?.join(RECOVERY_DIR))
+}
+
+/// This instance's folder inside the recovery folder. Its lock file stays locked while the
+/// app runs, so other instances leave the copies in it alone.
+pub struct RecoveryInstance {
+    dir: PathBuf,
+    lock: Option<fs::File>,
+}
+
+impl Drop for RecoveryInstance {
+    /// Removes the folder on a clean exit, when nothing in it needs recovering.
+    fn drop(&mut self) {
+        if !json_files(&self.dir).any(|_| true) {
+            self.lock.take();
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+}
+
+/// The folder for this instance's copies, created on first use.
+fn instance_dir(state: &mut AppState) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    if let Some(instance) = &state.recovery_instance {
+        return Ok(instance.dir.clone());
+    }
+
+    let root = recovery_dir(state)?;
+    fs::create_dir_all(&root)?;
+    let dir = tempfile::Builder::new().prefix("instance-").tempdir_in(&root)?.keep();
+    let lock = fs::File::create(dir.join(LOCK_FILE))?;
+    lock.try_lock()?;
+
+    state.recovery_instance = Some(RecoveryInstance { dir: dir.clone(), lock: Some(lock) });
+    Ok(dir)
+}
+
+fn recovery_file(dir: &Path, document: &Document) -> PathBuf {
+    dir.join(format!("{}.json", document.id))
+}
+
+/// Whether the instance that owns `dir` is still running and holding its lock.
+fn instance_is_running(dir: &Path) -> bool {
+    fs::File::open(dir.join(LOCK_FILE))
+        .is_ok_and(|lock| matches!(lock.try_lock(), Err(fs::TryLockError::WouldBlock)))
+}
+
+/// Removes a recovery copy, and the folder of the instance that left it once it is empty.
+fn remove_recovery_file(path: &Path) {
+    let _ = fs::remove_file(path);
+
+    if let Some(dir) = path.parent()
+        && dir.file_name().is_some_and(|name| name.to_string_lossy().starts_with("instance-"))
+        && !json_files(dir).any(|_| true)
+        && !instance_is_running(dir)
+    {
+        let _ = fs::remove_dir_all(dir);
+    }
+}
+
+fn json_files(dir: &Path) -> impl Iterator<Item = PathBuf> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+}
+
+fn content_hash(document: &Document) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    document.current_content.hash(&mut hasher);
+    document.current_file_path.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Writes dirty buffers to the recovery folder once the configured interval has elapsed.
+pub fn autosave_if_due(state: &mut AppState, ctx: &egui::Context) {
+    // Wait until the user decided what to do with the previous session's buffers.
+    if !state.settings_state.current.autosave_enabled || !state.recovered_documents.is_empty() {
+        return;
+    }
+
+    let interval = state.settings_state.current.autosave_interval_secs.max(1) as f64;
+    let now = ctx.input(|i| i.time);
+
+    if now - state.last_autosave >= interval {
+        state.last_autosave = now;
+        autosave(state);
+    }
+
+    ctx.request_repaint_after(std::time::Duration::from_secs_f64(interval));
+}
+
+/// Writes a recovery copy of every dirty document and removes the copies of clean ones.
+pub fn autosave(state: &mut AppState) {
+    for index in 0..state.notepad_state.documents.len() {
+        let document = &state.notepad_state.documents[index];
+
+        if !document.is_dirty() {
+            if state.notepad_state.documents[index].autosave_hash.is_some() {
+                discard_recovery(state, &state.notepad_state.documents[index]);
+                state.notepad_state.documents[index].autosave_hash = None;
+            }
+            continue;
+        }
+
+        let hash = content_hash(document);
+        if document.autosave_hash == Some(hash) {
+            continue;
+        }
+
+        let dir = match instance_dir(state) {
+            Ok(dir) => dir,
+            Err(e) => {
+                error!("ERROR: creating recovery folder -> {}", e);
+                return;
+            }
+        };
+        let document = &state.notepad_state.documents[index];
+        let path = recovery_file(&dir, document);
+
+        let recovered = RecoveredDocument {
+            file_path: document.current_file_path.clone(),
+            content: document.current_content.clone(),
+            encoding: document.encoding.encoding.name().to_string(),
+            bom: document.encoding.bom,
+            line_ending: document.line_ending,
+            recovery_file: PathBuf::new(),
+        };
+
+        let result = serde_json::to_vec(&recovered)
+            .map_err(|e| e.into())
+            .and_then(|json| -> Result<(), Box<dyn std::error::Error>> {
+                write_atomic(&path, &json, 0)?;
+                Ok(())
+            });
+
+        match result {
+            Ok(()) => state.notepad_state.documents[index].autosave_hash = Some(hash),
+            Err(e) => error!("ERROR: writing recovery file {:?} -> {}", path, e),
+        }
+    }
+}
+
+/// Removes the recovery copy of a document that is being closed or was just saved.
+pub fn discard_recovery(state: &AppState, document: &Document) {
+    if document.autosave_hash.is_some()
+        && let Some(instance) = &state.recovery_instance
+    {
+        let _ = fs::remove_file(recovery_file(&instance.dir, document));
+    }
+}
+
+/// Removes the recovery copy of the active document right after it was saved.
+pub fn discard_active_recovery(state: &mut AppState) {
+    discard_recovery(state, state.notepad_state.active());
+    state.notepad_state.active_mut().autosave_hash = None;
+}
+
+/// Reads the buffers left behind by previous sessions that didn't exit cleanly, skipping
+/// those of instances that are still running.
+pub fn load_recovered_documents(state: &AppState) -> Vec<RecoveredDocument> {
+    let Ok(entries) = recovery_dir(state).and_then(|dir| Ok(fs::read_dir(dir)?)) else {
+        return Vec::new();
+    };
+
+    let mut paths = Vec::new();
+    for path in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+        if path.is_dir() {
+            if !instance_is_running(&path) {
+                paths.extend(json_files(&path));
+            }
+        } else if path.extension().is_some_and(|extension| extension == "json") {
+            // Copies written by older versions straight into the recovery folder.
+            paths.push(path);
+        }
+    }
+
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let json = fs::read_to_string(&path).ok()?;
+            let mut recovered: RecoveredDocument = serde_json::from_str(&json).ok()?;
+            recovered.recovery_file = path;
+            Some(recovered)
+        })
+        .collect()
+}
+
+pub fn restore_recovered_documents(state: &mut AppState) {
+    let recovered = std::mem::take(&mut state.recovered_documents);
+    info!("Restoring {} recovered documents", recovered.len());
+
+    for document in recovered {
+        remove_recovery_file(&document.recovery_file);
+
+        // A file reopened by the session is replaced by its unsaved version.
+        if let Some(index) = state.notepad_state.documents.iter().position(|open| {
+            open.current_file_path.is_some() && open.current_file_path == document.file_path
+        }) {
+            state.notepad_state.documents[index] = document.into_document();
+            state.notepad_state.active_document = index;
+        } else if state.notepad_state.active().is_pristine() {
+            *state.notepad_state.active_mut() = document.into_document();
+        } else {
+            state.notepad_state.documents.push(document.into_document());
+            state.notepad_state.active_document = state.notepad_state.documents.len() - 1;
+        }
+    }
+
+    autosave(state);
+}
+
+pub fn discard_recovered_documents(state: &mut AppState) {
+    for document in std::mem::take(&mut state.recovered_documents) {
+        remove_recovery_file(&document.recovery_file);
+    }
+}
+
+pub fn show_recovery_dialog(ctx: &egui::Context, state: &mut AppState) {
+    if state.recovered_documents.is_empty() {
+        return;
+    }
+
+    let untitled = state.text("notepad.tab.untitled");
+    let mut restore = false;
+    let mut discard = false;
+
+    egui::Window::new(state.text("recovery.dialog.title"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            ui.label(state.text("recovery.dialog.message"));
+            ui.add_space(6.0);
+
+            for document in &state.recovered_documents {
+                let name = document
+                    .file_path
+                    .as_ref()
+                    .map(|path| path.display().to_string())
+                    .unwrap_or_else(|| untitled.clone());
+                ui.label(format!("• {}", name));
+            }
+
+            ui.add_space(6.0);
+            ui.horizontal(|ui| {
+                if ui.button(state.text("recovery.dialog.button.discard")).clicked() {
+                    discard = true;
+                }
+
+                if ui.button(state.text("recovery.dialog.button.restore")).clicked() {
+                    restore = true;
+                }
+            });
+        });
+
+    if restore {
+        restore_recovered_documents(state);
+    } else if discard {
+        discard_recovered_documents(state);
+    }
+}
@@ -0,0 +1,26 @@
+use crate::app_state::AppState;
+use eframe::egui;
+
+pub fn show_error_dialog(ctx: &egui::Context, state: &mut AppState) {
+    let Some(message) = &state.error_message else {
+        return;
+    };
+
+    let mut dismissed = false;
+
+    egui::Window::new(state.text("error.dialog.title"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            ui.label(message);
+
+            if ui.button(state.text("error.dialog.button.ok")).clicked() {
+                dismissed = true;
+            }
+        });
+
+    if dismissed {
+        state.error_message = None;
+    }
+}
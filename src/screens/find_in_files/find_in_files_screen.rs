@@ -0,0 +1,247 @@
+use crate::app_state::AppState;
+use crate::navigator::Screen;
+use crate::screens::find_in_files::search_job::{SearchJob, SearchMessage};
+use crate::screens::notepad::commands::{go_to_line, open_path, report_open_error};
+use crate::screens::notepad::search::build_regex;
+use eframe::egui;
+use rfd::FileDialog;
+use std::path::{Path, PathBuf};
+
+pub fn find_in_files_screen(state: &mut AppState, ctx: &egui::Context, frame: &mut eframe::Frame) {
+    poll_search(state);
+    find_in_files_content(state, ctx, frame);
+}
+
+fn find_in_files_content(state: &mut AppState, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    egui::TopBottomPanel::top("find_in_files_form").show(ctx, |ui| {
+        ui.add_space(10.0);
+        ui.horizontal(|ui| {
+            if ui.button("⏴").on_hover_text(state.text("find.in.files.back")).clicked() {
+                state.screen = Screen::Notepad;
+            }
+            ui.heading(state.text("find.in.files.title"));
+        });
+        ui.add_space(10.0);
+
+        egui::Grid::new("find_in_files_grid")
+            .num_columns(2)
+            .spacing([10.0, 6.0])
+            .show(ui, |ui| {
+                ui.label(state.text("find.in.files.query"));
+                let query_response = ui.add(
+                    egui::TextEdit::singleline(&mut state.find_in_files_state.query)
+                        .desired_width(400.0),
+                );
+                if query_response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    start_search(state, ctx);
+                }
+                ui.end_row();
+
+                ui.label(state.text("find.in.files.folder"));
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut state.find_in_files_state.folder)
+                            .desired_width(400.0),
+                    );
+                    if ui.button(state.text("settings.default.path.button")).clicked()
+                        && let Some(path) = FileDialog::new()
+                            .set_title(state.text("settings.default.path.select.folder"))
+                            .pick_folder()
+                    {
+                        state.find_in_files_state.folder = path.to_string_lossy().into_owned();
+                    }
+                });
+                ui.end_row();
+
+                ui.label(state.text("find.in.files.include"));
+                ui.add(
+                    egui::TextEdit::singleline(&mut state.find_in_files_state.include)
+                        .hint_text("*.md, *.txt")
+                        .desired_width(400.0),
+                );
+                ui.end_row();
+
+                ui.label(state.text("find.in.files.exclude"));
+                ui.add(
+                    egui::TextEdit::singleline(&mut state.find_in_files_state.exclude)
+                        .desired_width(400.0),
+                );
+                ui.end_row();
+            });
+
+        ui.horizontal(|ui| {
+            let match_case_text = state.text("notepad.find.match.case");
+            let whole_word_text = state.text("notepad.find.whole.word");
+            let regex_text = state.text("notepad.find.regex");
+            let options = &mut state.find_in_files_state.options;
+            ui.checkbox(&mut options.match_case, match_case_text);
+            ui.checkbox(&mut options.whole_word, whole_word_text);
+            ui.checkbox(&mut options.use_regex, regex_text);
+        });
+
+        ui.add_space(6.0);
+        ui.horizontal(|ui| {
+            let searching = state.find_in_files_state.job.is_some();
+
+            if ui
+                .add_enabled(!searching, egui::Button::new(state.text("find.in.files.button.search")))
+                .clicked()
+            {
+                start_search(state, ctx);
+            }
+
+            if ui
+                .add_enabled(searching, egui::Button::new(state.text("find.in.files.button.cancel")))
+                .clicked()
+                && let Some(job) = &state.find_in_files_state.job
+            {
+                job.cancel();
+            }
+
+            if searching {
+                ui.spinner();
+            }
+
+            ui.label(search_summary(state));
+        });
+
+        if let Some(error) = &state.find_in_files_state.error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+        ui.add_space(6.0);
+    });
+
+    egui::CentralPanel::default().show(ctx, |ui| {
+        let mut open_hit: Option<(PathBuf, usize)> = None;
+        let folder = PathBuf::from(&state.find_in_files_state.folder);
+
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                for result in &state.find_in_files_state.results {
+                    let title = format!(
+                        "{} ({})",
+                        display_path(&result.path, &folder),
+                        result.hits.len()
+                    );
+
+                    egui::CollapsingHeader::new(title)
+                        .id_salt(&result.path)
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            for hit in &result.hits {
+                                let label = format!("{:>6}: {}", hit.line, hit.preview);
+                                if ui
+                                    .selectable_label(false, egui::RichText::new(label).monospace())
+                                    .clicked()
+                                {
+                                    open_hit = Some((result.path.clone(), hit.line));
+                                }
+                            }
+                        });
+                }
+            });
+
+        if let Some((path, line)) = open_hit {
+            match open_path(state, path.clone()) {
+                Ok(()) => {
+                    go_to_line(state, line);
+                    state.screen = Screen::Notepad;
+                }
+                Err(e) => report_open_error(state, &path, &e),
+            }
+        }
+    });
+}
+
+/// Fills in the default folder the first time the screen is shown.
+pub fn prepare_find_in_files(state: &mut AppState) {
+    if state.find_in_files_state.folder.is_empty() {
+        state.find_in_files_state.folder = state.settings_state.current.default_path.clone();
+    }
+}
+
+fn start_search(state: &mut AppState, ctx: &egui::Context) {
+    let folder_error = state.text("find.in.files.error.folder");
+    let find_in_files = &mut state.find_in_files_state;
+
+    find_in_files.job = None;
+    find_in_files.results.clear();
+    find_in_files.files_scanned = 0;
+    find_in_files.cancelled = false;
+    find_in_files.error = None;
+
+    if find_in_files.query.is_empty() {
+        return;
+    }
+
+    let folder = PathBuf::from(&find_in_files.folder);
+    if !folder.is_dir() {
+        find_in_files.error = Some(folder_error);
+        return;
+    }
+
+    let regex = match build_regex(&find_in_files.query, &find_in_files.options) {
+        Ok(regex) => regex,
+        Err(e) => {
+            find_in_files.error = Some(e.to_string());
+            return;
+        }
+    };
+
+    match SearchJob::start(
+        ctx.clone(),
+        folder,
+        regex,
+        &find_in_files.include,
+        &find_in_files.exclude,
+    ) {
+        Ok(job) => find_in_files.job = Some(job),
+        Err(e) => find_in_files.error = Some(e.to_string()),
+    }
+}
+
+fn poll_search(state: &mut AppState) {
+    let find_in_files = &mut state.find_in_files_state;
+    let Some(job) = &find_in_files.job else {
+        return;
+    };
+
+    for message in job.poll() {
+        match message {
+            SearchMessage::File(result) => find_in_files.results.push(result),
+            SearchMessage::Finished { files_scanned, cancelled } => {
+                find_in_files.files_scanned = files_scanned;
+                find_in_files.cancelled = cancelled;
+                find_in_files.job = None;
+                break;
+            }
+        }
+    }
+}
+
+fn search_summary(state: &AppState) -> String {
+    let find_in_files = &state.find_in_files_state;
+    let hits: usize = find_in_files.results.iter().map(|result| result.hits.len()).sum();
+
+    let key = if find_in_files.job.is_some() {
+        "find.in.files.status.searching"
+    } else if find_in_files.cancelled {
+        "find.in.files.status.cancelled"
+    } else {
+        "find.in.files.status.done"
+    };
+
+    state
+        .text(key)
+        .replace("{hits}", &hits.to_string())
+        .replace("{files}", &find_in_files.results.len().to_string())
+        .replace("{scanned}", &find_in_files.files_scanned.to_string())
+}
+
+fn display_path(path: &Path, folder: &Path) -> String {
+    path.strip_prefix(folder)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .into_owned()
+}
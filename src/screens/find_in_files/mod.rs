@@ -0,0 +1,2 @@
+pub mod find_in_files_screen;
+pub mod search_job;
\ No newline at end of file
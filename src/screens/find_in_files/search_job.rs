@@ -0,0 +1,251 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use walkdir::WalkDir;
+
+const MAX_PREVIEW_CHARS: usize = 200;
+const MAX_HITS_PER_FILE: usize = 1000;
+
+pub struct LineHit {
+    /// 1-based line number.
+    pub line: usize,
+    pub preview: String,
+}
+
+pub struct FileResult {
+    pub path: PathBuf,
+    pub hits: Vec<LineHit>,
+}
+
+pub enum SearchMessage {
+    File(FileResult),
+    Finished { files_scanned: usize, cancelled: bool },
+}
+
+/// A folder search running on a background thread.
+pub struct SearchJob {
+    receiver: Receiver<SearchMessage>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl SearchJob {
+    pub fn start(
+        ctx: eframe::egui::Context,
+        folder: PathBuf,
+        regex: Regex,
+        include: &str,
+        exclude: &str,
+    ) -> Result<Self, globset::Error> {
+        let include = build_glob_set(include)?;
+        let exclude = build_glob_set(exclude)?;
+        let (sender, receiver) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+
+        let worker_cancel = cancel.clone();
+        thread::spawn(move || {
+            search_folder(&folder, &regex, &include, &exclude, &worker_cancel, &sender, &ctx);
+        });
+
+        Ok(Self { receiver, cancel })
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn poll(&self) -> Vec<SearchMessage> {
+        self.receiver.try_iter().collect()
+    }
+}
+
+impl Drop for SearchJob {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Builds a glob set from a comma-separated list; `None` when the list is empty.
+/// Patterns without a `/` match a name at any depth, so `target` also skips `crates/a/target`.
+fn build_glob_set(patterns: &str) -> Result<Option<GlobSet>, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    let mut is_empty = true;
+
+    for pattern in patterns.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        if pattern.contains('/') {
+            builder.add(Glob::new(pattern)?);
+        } else {
+            builder.add(Glob::new(&format!("**/{}", pattern))?);
+        }
+        is_empty = false;
+    }
+
+    if is_empty {
+        Ok(None)
+    } else {
+        builder.build().map(Some)
+    }
+}
+
+fn search_folder(
+    folder: &Path,
+    regex: &Regex,
+    include: &Option<GlobSet>,
+    exclude: &Option<GlobSet>,
+    cancel: &AtomicBool,
+    sender: &Sender<SearchMessage>,
+    ctx: &eframe::egui::Context,
+) {
+    let mut files_scanned = 0;
+
+    let entries = WalkDir::new(folder)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|entry| {
+            let relative = entry.path().strip_prefix(folder).unwrap_or(entry.path());
+            entry.depth() == 0 || !exclude.as_ref().is_some_and(|set| set.is_match(relative))
+        })
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file());
+
+    for entry in entries {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+
+        let relative = entry.path().strip_prefix(folder).unwrap_or(entry.path());
+        if include.as_ref().is_some_and(|set| !set.is_match(relative)) {
+            continue;
+        }
+
+        files_scanned += 1;
+
+        if let Some(result) = search_file(entry.path(), regex)
+            && sender.send(SearchMessage::File(result)).is_ok()
+        {
+            ctx.request_repaint();
+        }
+    }
+
+    let _ = sender.send(SearchMessage::Finished {
+        files_scanned,
+        cancelled: cancel.load(Ordering::Relaxed),
+    });
+    ctx.request_repaint();
+}
+
+fn search_file(path: &Path, regex: &Regex) -> Option<FileResult> {
+    let bytes = fs::read(path).ok()?;
+
+    // Skip binary files.
+    if bytes.iter().take(8000).any(|byte| *byte == 0) {
+        return None;
+    }
+
+    let content = String::from_utf8_lossy(&bytes);
+    let hits: Vec<LineHit> = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| regex.is_match(line))
+        .take(MAX_HITS_PER_FILE)
+        .map(|(index, line)| LineHit {
+            line: index + 1,
+            preview: line.trim().chars().take(MAX_PREVIEW_CHARS).collect(),
+        })
+        .collect();
+
+    if hits.is_empty() {
+        None
+    } else {
+        Some(FileResult {
+            path: path.to_path_buf(),
+            hits,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(folder: &Path, include: &str, exclude: &str) -> Vec<PathBuf> {
+        let (sender, receiver) = mpsc::channel();
+        search_folder(
+            folder,
+            &Regex::new("needle").unwrap(),
+            &build_glob_set(include).unwrap(),
+            &build_glob_set(exclude).unwrap(),
+            &AtomicBool::new(false),
+            &sender,
+            &eframe::egui::Context::default(),
+        );
+
+        let mut paths: Vec<PathBuf> = receiver
+            .try_iter()
+            .filter_map(|message| match message {
+                SearchMessage::File(result) => {
+                    Some(result.path.strip_prefix(folder).unwrap().to_path_buf())
+                }
+                SearchMessage::Finished { .. } => None,
+            })
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    fn write(folder: &Path, path: &str) {
+        let path = folder.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "a needle in a haystack\n").unwrap();
+    }
+
+    #[test]
+    fn empty_pattern_list_has_no_glob_set() {
+        assert!(build_glob_set("").unwrap().is_none());
+        assert!(build_glob_set(" , ").unwrap().is_none());
+    }
+
+    #[test]
+    fn bare_patterns_match_at_any_depth() {
+        let set = build_glob_set("*.rs, target").unwrap().unwrap();
+        assert!(set.is_match("main.rs"));
+        assert!(set.is_match("src/screens/main.rs"));
+        assert!(set.is_match("target"));
+        assert!(set.is_match("crates/app/target"));
+        assert!(!set.is_match("targets"));
+    }
+
+    #[test]
+    fn patterns_with_a_slash_match_from_the_folder() {
+        let set = build_glob_set("src/*.rs").unwrap().unwrap();
+        assert!(set.is_match("src/main.rs"));
+        assert!(!set.is_match("crates/src/main.rs"));
+    }
+
+    #[test]
+    fn nested_excluded_folders_are_skipped() {
+        let folder = tempfile::tempdir().unwrap();
+        write(folder.path(), "main.rs");
+        write(folder.path(), "target/debug.rs");
+        write(folder.path(), "crates/app/lib.rs");
+        write(folder.path(), "crates/app/target/build.rs");
+
+        assert_eq!(
+            search(folder.path(), "", "target"),
+            [PathBuf::from("crates/app/lib.rs"), PathBuf::from("main.rs")]
+        );
+    }
+
+    #[test]
+    fn include_filters_files_in_subfolders() {
+        let folder = tempfile::tempdir().unwrap();
+        write(folder.path(), "notes.txt");
+        write(folder.path(), "src/main.rs");
+
+        assert_eq!(search(folder.path(), "*.rs", ""), [PathBuf::from("src/main.rs")]);
+    }
+}
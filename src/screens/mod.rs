@@ -1,2 +1,4 @@
+pub mod error_dialog;
+pub mod find_in_files;
 pub mod notepad;
 pub mod settings;
\ No newline at end of file
@@ -0,0 +1,151 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Writes `bytes` to `path` without ever leaving a truncated file behind.
+///
+/// The data goes to a temporary file in the same directory, is flushed to disk and then
+/// renamed over the target, keeping the original file's permissions. When `backups` is
+/// greater than zero the previous version is kept as `<name>.bak`, `<name>.bak.1`, ...
+pub fn write_atomic(path: &Path, bytes: &[u8], backups: usize) -> io::Result<()> {
+    // Write through symlinks instead of replacing them.
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let directory = match target.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    let (temp_path, mut temp_file) = create_temp_file(&directory, &target)?;
+
+    let result = (|| {
+        temp_file.write_all(bytes)?;
+        temp_file.sync_all()?;
+
+        if let Ok(metadata) = fs::metadata(&target) {
+            fs::set_permissions(&temp_path, metadata.permissions())?;
+
+            if backups > 0 {
+                rotate_backups(&target, backups)?;
+            }
+        }
+
+        fs::rename(&temp_path, &target)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+        return result;
+    }
+
+    // Make the rename itself durable; not supported on every platform.
+    if let Ok(directory) = File::open(&directory) {
+        let _ = directory.sync_all();
+    }
+
+    Ok(())
+}
+
+fn create_temp_file(directory: &Path, target: &Path) -> io::Result<(PathBuf, File)> {
+    let name = target
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let mut attempt = 0;
+    loop {
+        let temp_path = directory.join(format!(".{}.{}.{}.tmp", name, std::process::id(), attempt));
+
+        match OpenOptions::new().write(true).create_new(true).open(&temp_path) {
+            Ok(file) => return Ok((temp_path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists && attempt < 100 => attempt += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn backup_path(target: &Path, index: usize) -> PathBuf {
+    let mut name = target.as_os_str().to_owned();
+    if index == 0 {
+        name.push(".bak");
+    } else {
+        name.push(format!(".bak.{}", index));
+    }
+    PathBuf::from(name)
+}
+
+/// Shifts existing backups one slot back, dropping the oldest, and copies the current file.
+fn rotate_backups(target: &Path, backups: usize) -> io::Result<()> {
+    for index in (0..backups - 1).rev() {
+        let from = backup_path(target, index);
+        if from.exists() {
+            fs::rename(&from, backup_path(target, index + 1))?;
+        }
+    }
+
+    fs::copy(target, backup_path(target, 0))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(path: &Path) -> String {
+        fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn creates_and_replaces_files_without_leftovers() {
+        let folder = tempfile::tempdir().unwrap();
+        let path = folder.path().join("notes.txt");
+
+        write_atomic(&path, b"first", 0).unwrap();
+        write_atomic(&path, b"second", 0).unwrap();
+
+        assert_eq!(read(&path), "second");
+        assert_eq!(fs::read_dir(folder.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn keeps_the_requested_number_of_backups() {
+        let folder = tempfile::tempdir().unwrap();
+        let path = folder.path().join("notes.txt");
+
+        for version in 1..=4 {
+            write_atomic(&path, version.to_string().as_bytes(), 2).unwrap();
+        }
+
+        assert_eq!(read(&path), "4");
+        assert_eq!(read(&backup_path(&path, 0)), "3");
+        assert_eq!(read(&backup_path(&path, 1)), "2");
+        assert!(!backup_path(&path, 2).exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_permissions_and_writes_through_symlinks() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let folder = tempfile::tempdir().unwrap();
+        let path = folder.path().join("script.sh");
+        let link = folder.path().join("link.sh");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o750)).unwrap();
+        symlink(&path, &link).unwrap();
+
+        write_atomic(&link, b"new", 0).unwrap();
+
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(read(&path), "new");
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o750);
+    }
+
+    #[test]
+    fn a_failed_write_leaves_the_original_alone() {
+        let folder = tempfile::tempdir().unwrap();
+        let missing = folder.path().join("missing").join("notes.txt");
+
+        assert!(write_atomic(&missing, b"data", 0).is_err());
+        assert_eq!(fs::read_dir(folder.path()).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,172 @@
+use crate::app_state::AppState;
+use crate::keybindings::{format_sequence, Action};
+use crate::shortcuts::run_action;
+use eframe::egui;
+use eframe::egui::text::{LayoutJob, TextFormat};
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+
+const MAX_RECENT_ACTIONS: usize = 10;
+
+struct Entry {
+    action: Action,
+    label: String,
+    /// Char indices of the label that matched the query.
+    matched: Vec<usize>,
+}
+
+/// Commands matching `query`, best match first; recently used commands win ties and lead
+/// the list while the query is empty.
+fn matching_entries(state: &AppState, query: &str) -> Vec<Entry> {
+    let matcher = SkimMatcherV2::default().ignore_case();
+    let query = query.trim();
+    let recent = &state.notepad_state.recent_actions;
+    let recent_rank = |action: Action| recent.iter().position(|recent| *recent == action).unwrap_or(usize::MAX);
+
+    let mut entries: Vec<(i64, Entry)> = Action::ALL
+        .into_iter()
+        .filter(|action| *action != Action::CommandPalette)
+        .filter_map(|action| {
+            let label = action.label(state);
+            let (score, matched) = if query.is_empty() {
+                (0, Vec::new())
+            } else {
+                matcher.fuzzy_indices(&label, query)?
+            };
+            Some((score, Entry { action, label, matched }))
+        })
+        .collect();
+
+    entries.sort_by(|(a_score, a), (b_score, b)| {
+        b_score
+            .cmp(a_score)
+            .then_with(|| recent_rank(a.action).cmp(&recent_rank(b.action)))
+    });
+    entries.into_iter().map(|(_, entry)| entry).collect()
+}
+
+fn highlighted_label(ui: &egui::Ui, entry: &Entry) -> LayoutJob {
+    let font_id = egui::TextStyle::Button.resolve(ui.style());
+    let plain = TextFormat::simple(font_id, ui.visuals().text_color());
+    let matched = TextFormat {
+        color: ui.visuals().strong_text_color(),
+        underline: egui::Stroke::new(1.0, ui.visuals().strong_text_color()),
+        ..plain.clone()
+    };
+
+    let mut job = LayoutJob::default();
+    for (index, c) in entry.label.chars().enumerate() {
+        let format = if entry.matched.contains(&index) { &matched } else { &plain };
+        job.append(c.encode_utf8(&mut [0; 4]), 0.0, format.clone());
+    }
+    job
+}
+
+fn run_command(state: &mut AppState, action: Action) {
+    state.notepad_state.command_palette = None;
+
+    let recent = &mut state.notepad_state.recent_actions;
+    recent.retain(|recent| *recent != action);
+    recent.insert(0, action);
+    recent.truncate(MAX_RECENT_ACTIONS);
+
+    run_action(state, action);
+}
+
+pub fn show_command_palette(ctx: &egui::Context, state: &mut AppState) {
+    let Some(palette) = &state.notepad_state.command_palette else {
+        return;
+    };
+
+    let entries = matching_entries(state, &palette.query);
+    // The click that opened the palette must not close it again.
+    let just_opened = palette.focus_query;
+    let hint = state.text("notepad.command.palette.hint");
+    let empty_text = state.text("notepad.command.palette.empty");
+
+    // Taken before the query field sees them, so they drive the list instead.
+    let (up, down, enter, escape) = ctx.input_mut(|i| {
+        (
+            i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+        )
+    });
+
+    let mut chosen = None;
+    let mut closed = escape;
+
+    let window = egui::Window::new("command_palette")
+        .title_bar(false)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_TOP, [0.0, 40.0])
+        .show(ctx, |ui| {
+            ui.set_width(480.0);
+            let Some(palette) = &mut state.notepad_state.command_palette else {
+                return;
+            };
+
+            if down {
+                palette.selected = (palette.selected + 1).min(entries.len().saturating_sub(1));
+            }
+            if up {
+                palette.selected = palette.selected.saturating_sub(1);
+            }
+
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut palette.query)
+                    .hint_text(&hint)
+                    .desired_width(f32::INFINITY),
+            );
+            if palette.focus_query {
+                response.request_focus();
+                palette.focus_query = false;
+            }
+            if response.changed() {
+                palette.selected = 0;
+            }
+            palette.selected = palette.selected.min(entries.len().saturating_sub(1));
+
+            if enter {
+                chosen = entries.get(palette.selected).map(|entry| entry.action);
+            }
+
+            ui.separator();
+
+            if entries.is_empty() {
+                ui.weak(&empty_text);
+                return;
+            }
+
+            egui::ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+                for (index, entry) in entries.iter().enumerate() {
+                    let selected = index == palette.selected;
+                    let mut button = egui::Button::selectable(selected, highlighted_label(ui, entry))
+                        .min_size(egui::vec2(ui.available_width(), 0.0));
+                    if let Some(sequence) = state.keybindings_state.current.sequences(entry.action).first() {
+                        button = button.shortcut_text(format_sequence(sequence));
+                    }
+
+                    let response = ui.add(button);
+                    if selected && (up || down) {
+                        response.scroll_to_me(None);
+                    }
+                    if response.clicked() {
+                        chosen = Some(entry.action);
+                    }
+                }
+            });
+        });
+
+    if !just_opened && window.is_some_and(|window| window.response.clicked_elsewhere()) {
+        closed = true;
+    }
+
+    if let Some(action) = chosen {
+        run_command(state, action);
+    } else if closed {
+        state.notepad_state.command_palette = None;
+    }
+}
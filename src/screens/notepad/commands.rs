@@ -1,12 +1,34 @@
-use crate::app_state::{AppState, PendingAction};
-use log::{error, info};
+use crate::app_state::{
+    font_data, AppState, Document, EncodingDialog, EncodingDialogMode, NotepadState, PendingAction,
+    SearchKey,
+};
+use crate::recent_files::add_recent_file;
+use crate::recovery::{discard_active_recovery, discard_recovery};
+use crate::screens::notepad::atomic_write::write_atomic;
+use crate::screens::notepad::encoding::{decode, decode_with, encode, TextEncoding, Unmappable};
+use crate::screens::notepad::file_watch::{confirm_overwrite_needed, DiskState};
+use crate::screens::notepad::history::{History, LineEndings};
+use crate::screens::notepad::html_export::{render_html, render_html_fragment, spans_in, HtmlOptions};
+use crate::screens::notepad::line_ending::{self, LineEnding};
+use crate::screens::notepad::pdf_export::{render_pdf, PdfOptions};
+use crate::screens::notepad::syntax::{LanguageMode, Span};
+use crate::screens::notepad::search::{
+    build_regex, byte_index, char_index, find_matches, line_char_range, replace_all, replace_match,
+};
+use eframe::egui;
+use log::{info, warn};
 use rfd::FileDialog;
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::io::{self, Write};
+use std::process::Command;
+use std::sync::mpsc;
+use std::thread;
 
 pub fn new_file(state: &mut AppState) {
-    state.notepad_state.current_content = String::new();
-    state.notepad_state.current_file_path = None;
-    state.notepad_state.file_content = None;
+    let document = Document::with_settings(&state.settings_state.current);
+    state.notepad_state.documents.push(document);
+    state.notepad_state.active_document = state.notepad_state.documents.len() - 1;
     state.notepad_state.pending_action = PendingAction::None;
 }
 
@@ -21,46 +43,777 @@ pub fn open_file(state: &mut AppState) {
     }
 
     if let Some(path) = dialog.pick_file()
+        && let Err(e) = open_path(state, path.clone())
     {
-        match fs::read_to_string(&path) {
-            Ok(content) => {
-                state.notepad_state.current_content = content;
-                state.notepad_state.current_file_path = Some(path);
-                state.notepad_state.file_content =
-                    Some(state.notepad_state.current_content.clone());
-                state.notepad_state.pending_action = PendingAction::None;
+        report_open_error(state, &path, &e);
+    }
+}
+
+pub fn report_open_error(state: &mut AppState, path: &Path, error: &std::io::Error) {
+    let message = format!("{} {}: {}", state.text("error.open.file"), path.display(), error);
+    state.report_error(message);
+}
+
+/// Opens `path` in a new tab, or switches to its tab if it is already open.
+pub fn open_path(state: &mut AppState, path: PathBuf) -> std::io::Result<()> {
+    if let Some(index) = open_document_index(state, &path) {
+        state.notepad_state.active_document = index;
+        add_recent_file(state, &path);
+        return Ok(());
+    }
+
+    let bytes = fs::read(&path)?;
+    let (content, encoding) = decode(&bytes);
+
+    if !state.notepad_state.active().is_pristine() {
+        new_file(state);
+    }
+
+    add_recent_file(state, &path);
+
+    let document = state.notepad_state.active_mut();
+    document.disk_state = Some(DiskState::new(&path, &bytes));
+    document.current_file_path = Some(path);
+    document.encoding = encoding;
+    load_content(document, content);
+    document.detect_language_mode();
+    state.notepad_state.pending_action = PendingAction::None;
+
+    Ok(())
+}
+
+/// Tab that has `path` open. Resolved paths are compared, so `./a.txt`, `dir/../a.txt`
+/// or a symlink find it too.
+fn open_document_index(state: &AppState, path: &Path) -> Option<usize> {
+    let canonical = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    state.notepad_state.documents.iter().position(|document| {
+        document.current_file_path.as_ref().is_some_and(|open| {
+            open == path || fs::canonicalize(open).is_ok_and(|open| open == canonical)
+        })
+    })
+}
+
+/// Sets content freshly read from disk, normalizing its line endings for the editor.
+fn load_content(document: &mut Document, content: String) {
+    let (line_ending, mixed) = line_ending::detect(&content);
+    if mixed {
+        warn!("Mixed line endings in {:?}", document.current_file_path);
+    }
+
+    document.set_content(line_ending::normalize(&content));
+    document.file_content = Some(document.current_content.clone());
+    document.line_ending = line_ending.unwrap_or(document.line_ending);
+    document.file_line_ending = Some(document.line_ending);
+    document.mixed_line_endings = mixed;
+    document.line_endings_unified = false;
+    document.history = History::default();
+}
+
+/// Reloads the active document from disk, decoding it with `encoding`.
+pub fn reopen_with_encoding(
+    state: &mut AppState,
+    encoding: &'static encoding_rs::Encoding,
+) -> std::io::Result<()> {
+    reload_from_disk(state.notepad_state.active_mut(), encoding)
+}
+
+/// Replaces the document's content with the file on disk, discarding unsaved changes.
+pub fn reload_from_disk(
+    document: &mut Document,
+    encoding: &'static encoding_rs::Encoding,
+) -> std::io::Result<()> {
+    let Some(path) = document.current_file_path.clone() else {
+        return Ok(());
+    };
+
+    let bytes = fs::read(&path)?;
+    let (content, text_encoding, had_errors) = decode_with(&bytes, encoding);
+    if had_errors {
+        info!("Invalid {} sequences replaced in {:?}", encoding.name(), path);
+    }
+
+    document.encoding = text_encoding;
+    document.disk_state = Some(DiskState::new(&path, &bytes));
+    load_content(document, content);
+
+    Ok(())
+}
+
+/// Saves the active document in another encoding, which it keeps once the file is written.
+pub fn save_with_encoding(state: &mut AppState, encoding: TextEncoding) -> Result<(), String> {
+    encode(&state.notepad_state.active().current_content, &encoding)
+        .map_err(|unmappable| unmappable_message(state, &unmappable))?;
+    save_in(state, encoding)
+}
+
+/// Places the cursor at a 1-based line and column of the active document, clamped to the line.
+pub fn go_to_position(state: &mut AppState, line: usize, column: usize) {
+    let document = state.notepad_state.active_mut();
+    if let Some(range) = line_char_range(&document.current_content, line) {
+        let cursor = (range.start + column.saturating_sub(1)).min(range.end);
+        document.pending_selection = Some(cursor..cursor);
+    }
+}
+
+/// Selects the given 1-based line in the active document and scrolls to it.
+pub fn go_to_line(state: &mut AppState, line: usize) {
+    let document = state.notepad_state.active_mut();
+    if let Some(range) = line_char_range(&document.current_content, line) {
+        document.pending_selection = Some(range);
+    }
+}
+
+pub fn save(state: &mut AppState) {
+    let encoding = state.notepad_state.active().encoding;
+    if let Err(message) = save_in(state, encoding) {
+        state.report_error(message);
+    }
+}
+
+/// Saves the active document to its file in `encoding`, asking for a name if it has none.
+fn save_in(state: &mut AppState, encoding: TextEncoding) -> Result<(), String> {
+    if state.notepad_state.active().read_only {
+        return Err(state.text("error.read.only"));
+    }
+
+    let Some(path) = state.notepad_state.active().current_file_path.clone() else {
+        return save_as_in(state, encoding);
+    };
+
+    if confirm_overwrite_needed(state, encoding) {
+        return Ok(());
+    }
+
+    write_active(state, &path, encoding)
+}
+
+/// Saves the active document under a new name and keeps editing that file.
+pub fn save_as(state: &mut AppState) {
+    let encoding = state.notepad_state.active().encoding;
+    if let Err(message) = save_as_in(state, encoding) {
+        state.report_error(message);
+    }
+}
+
+/// Also works for read-only documents, which become editable as the new file.
+fn save_as_in(state: &mut AppState, encoding: TextEncoding) -> Result<(), String> {
+    let Some(path) = pick_save_path(state, "notepad.menu.file.save.as") else {
+        return Ok(());
+    };
+
+    // Two tabs bound to one file would overwrite each other's saves.
+    if open_document_index(state, &path).is_some_and(|index| index != state.notepad_state.active_document) {
+        let name = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy();
+        return Err(state.text("error.save.open.elsewhere").replace("{file}", &name));
+    }
+
+    write_active(state, &path, encoding)?;
+
+    let document = state.notepad_state.active_mut();
+    document.current_file_path = Some(path.clone());
+    document.read_only = false;
+    if document.language_mode == LanguageMode::PlainText {
+        document.detect_language_mode();
+    }
+    add_recent_file(state, &path);
+    Ok(())
+}
+
+/// Writes the active document to `path` and marks it saved. The document only takes on
+/// `encoding` once the file was written, so a failed save leaves it as it was.
+fn write_active(state: &mut AppState, path: &Path, encoding: TextEncoding) -> Result<(), String> {
+    let backups = state.settings_state.current.backup_count;
+    let document = state.notepad_state.active_mut();
+    match write_document(path, document, &encoding, backups) {
+        Ok(disk_state) => {
+            info!("File saved in {:?}", path);
+            document.encoding = encoding;
+            mark_saved(document, disk_state);
+            discard_active_recovery(state);
+            Ok(())
+        }
+        Err(e) => Err(save_error_message(state, path, e)),
+    }
+}
+
+/// Writes the active document to another file without changing which file it is bound to.
+pub fn save_copy_as(state: &mut AppState) {
+    if let Some(path) = pick_save_path(state, "notepad.menu.file.save.copy") {
+        let backups = state.settings_state.current.backup_count;
+        let document = state.notepad_state.active();
+        match write_document(&path, document, &document.encoding, backups) {
+            Ok(_) => info!("Copy saved in {:?}", path),
+            Err(e) => {
+                let message = save_error_message(state, &path, e);
+                state.report_error(message);
             }
-            Err(e) => error!("ERROR: opening file -> {}", e),
         }
     }
 }
 
-pub fn save(state: &mut AppState) {
-    if let Some(path) = &state.notepad_state.current_file_path {
-        match fs::write(path, &state.notepad_state.current_content) {
-            Ok(_) => info!("File saved in {:?}", path),
-            Err(e) => error!("ERROR: opening file -> {}", e),
+fn pick_save_path(state: &AppState, title_key: &str) -> Option<PathBuf> {
+    let document = state.notepad_state.active();
+    let mut dialog = FileDialog::new()
+        .set_title(state.text(title_key))
+        .add_filter(state.text("notepad.file.dialog.filter.text.files"), &["txt"])
+        .add_filter(state.text("notepad.file.dialog.filter.all.files"), &["*"]);
+
+    match &document.current_file_path {
+        Some(path) => {
+            if let Some(directory) = path.parent() {
+                dialog = dialog.set_directory(directory);
+            }
+            if let Some(name) = path.file_name() {
+                dialog = dialog.set_file_name(name.to_string_lossy());
+            }
         }
-    } else {
-        let mut dialog = FileDialog::new()
-            .set_title(state.text("notepad.menu.file.save"))
-            .add_filter(state.text("notepad.file.dialog.filter.text.files"), &["txt"]);
+        None if !state.settings_state.current.default_path.is_empty() => {
+            dialog = dialog.set_directory(&state.settings_state.current.default_path);
+        }
+        None => {}
+    }
+
+    dialog.save_file()
+}
 
-        if !state.settings_state.current.default_path.is_empty() {
+/// Asks where to export the active document, suggesting its name with `extension`.
+fn pick_export_path(
+    state: &AppState,
+    title_key: &str,
+    filter_key: &str,
+    extension: &str,
+) -> Option<PathBuf> {
+    let document = state.notepad_state.active();
+    let stem = document
+        .current_file_path
+        .as_ref()
+        .and_then(|path| path.file_stem())
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| state.text("notepad.tab.untitled"));
+    let mut dialog = FileDialog::new()
+        .set_title(state.text(title_key))
+        .add_filter(state.text(filter_key), &[extension])
+        .set_file_name(format!("{}.{}", stem, extension));
+
+    match document.current_file_path.as_ref().and_then(|path| path.parent()) {
+        Some(directory) => dialog = dialog.set_directory(directory),
+        None if !state.settings_state.current.default_path.is_empty() => {
             dialog = dialog.set_directory(&state.settings_state.current.default_path);
         }
+        None => {}
+    }
 
-        if let Some(path) = dialog.save_file() {
-            match fs::write(&path, &state.notepad_state.current_content) {
-                Ok(_) => {
-                    info!("File saved in {:?}", path);
-                    state.notepad_state.current_file_path = Some(path);
-                    state.notepad_state.file_content =
-                        Some(state.notepad_state.current_content.clone());
-                    state.notepad_state.pending_action = PendingAction::None;
-                }
-                Err(e) => error!("ERROR: saving file -> {}", e),
-            }
+    dialog.save_file()
+}
+
+fn render_active_pdf(state: &AppState) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let settings = &state.settings_state.current;
+    let document = state.notepad_state.active();
+    let options = PdfOptions {
+        font: font_data(&settings.font_name),
+        font_size: settings.font_size,
+        paper_size: settings.paper_size,
+        margin_mm: settings.print_margin_mm,
+        line_numbers: settings.print_line_numbers,
+        header_footer: settings.print_header_footer,
+        title: document.title(&state.text("notepad.tab.untitled")),
+        date: printpdf::OffsetDateTime::now_utc().date().to_string(),
+        page_label: state.text("notepad.print.page"),
+    };
+    render_pdf(&document.current_content, &options)
+}
+
+pub fn export_pdf(state: &mut AppState) {
+    let Some(path) = pick_export_path(
+        state,
+        "notepad.menu.file.export.pdf",
+        "notepad.file.dialog.filter.pdf.files",
+        "pdf",
+    ) else {
+        return;
+    };
+
+    match render_active_pdf(state).and_then(|bytes| Ok(write_atomic(&path, &bytes, 0)?)) {
+        Ok(()) => info!("PDF exported to {:?}", path),
+        Err(e) => {
+            let message = format!("{} {}: {}", state.text("error.export.file"), path.display(), e);
+            state.report_error(message);
+        }
+    }
+}
+
+/// Sends the active document to the default printer as a PDF, through `lp`/`lpr` on Unix
+/// and the shell's print verb on Windows. The spooler runs on a background thread and the
+/// temporary PDF is removed once it has been handed over.
+pub fn print_document(state: &mut AppState) {
+    let file = render_active_pdf(state).and_then(|bytes| {
+        let mut file = tempfile::Builder::new()
+            .prefix("notepadr-print-")
+            .suffix(".pdf")
+            .tempfile()?;
+        file.write_all(&bytes)?;
+        file.flush()?;
+        Ok(file)
+    });
+
+    match file {
+        Ok(file) => {
+            let (sender, receiver) = mpsc::channel();
+            thread::spawn(move || {
+                let result = send_to_printer(file.path()).map_err(|e| e.to_string());
+                // Dropping the file deletes it; the spooler keeps its own copy.
+                drop(file);
+                let _ = sender.send(result);
+            });
+            state.notepad_state.print_jobs.push(receiver);
+        }
+        Err(e) => {
+            let message = format!("{}: {}", state.text("error.print"), e);
+            state.report_error(message);
         }
     }
 }
+
+/// Reports print jobs that have finished and keeps polling while any are still running.
+pub fn poll_print_jobs(state: &mut AppState, ctx: &egui::Context) {
+    if state.notepad_state.print_jobs.is_empty() {
+        return;
+    }
+
+    let mut errors = Vec::new();
+    state.notepad_state.print_jobs.retain(|receiver| match receiver.try_recv() {
+        Ok(Ok(())) => {
+            info!("Document sent to the printer");
+            false
+        }
+        Ok(Err(e)) => {
+            errors.push(e);
+            false
+        }
+        Err(mpsc::TryRecvError::Empty) => true,
+        Err(mpsc::TryRecvError::Disconnected) => false,
+    });
+
+    for e in errors {
+        let message = format!("{}: {}", state.text("error.print"), e);
+        state.report_error(message);
+    }
+    if !state.notepad_state.print_jobs.is_empty() {
+        ctx.request_repaint_after(std::time::Duration::from_millis(250));
+    }
+}
+
+#[cfg(unix)]
+fn send_to_printer(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let output = match Command::new("lp").arg(path).output() {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Command::new("lpr").arg(path).output()?,
+        output => output?,
+    };
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string().into())
+    }
+}
+
+#[cfg(windows)]
+fn send_to_printer(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let output = Command::new("powershell")
+        .args(["-NoProfile", "-Command", "Start-Process -Verb Print -Wait -FilePath $args[0]"])
+        .arg(path)
+        .output()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string().into())
+    }
+}
+
+/// Text, tokens and styling for exporting the active document's selection, or the whole
+/// document when nothing is selected.
+fn active_html_source(state: &mut AppState) -> (String, Vec<Span>, HtmlOptions<'static>) {
+    let settings = &state.settings_state.current;
+    // "Default" is egui's built-in font, which the export replaces with Roboto.
+    let font_name = match settings.font_name.as_str() {
+        "Default" => "Roboto".to_string(),
+        name => name.to_string(),
+    };
+    let options = HtmlOptions {
+        title: state.notepad_state.active().title(&state.text("notepad.tab.untitled")),
+        font_name,
+        font: font_data(&settings.font_name),
+        font_size: settings.font_size,
+        dark_mode: settings.dark_mode,
+    };
+
+    let document = state.notepad_state.active_mut();
+    let selection = document.selection();
+    let text = &document.current_content;
+    let range = if selection.is_empty() {
+        0..text.len()
+    } else {
+        byte_index(text, selection.start)..byte_index(text, selection.end)
+    };
+
+    let spans = if document.language_mode == LanguageMode::PlainText {
+        Vec::new()
+    } else {
+        let spans = document.highlight_cache.spans(document.language_mode, document.revision, text);
+        spans_in(spans, range.clone())
+    };
+    (text[range].to_string(), spans, options)
+}
+
+pub fn export_html(state: &mut AppState) {
+    let Some(path) = pick_export_path(
+        state,
+        "notepad.menu.file.export.html",
+        "notepad.file.dialog.filter.html.files",
+        "html",
+    ) else {
+        return;
+    };
+
+    let (text, spans, options) = active_html_source(state);
+    let html = render_html(&text, &spans, &options);
+    match write_atomic(&path, html.as_bytes(), 0) {
+        Ok(()) => info!("HTML exported to {:?}", path),
+        Err(e) => {
+            let message = format!("{} {}: {}", state.text("error.export.file"), path.display(), e);
+            state.report_error(message);
+        }
+    }
+}
+
+/// Puts the selection (or the whole document) on the clipboard as styled HTML, with the
+/// plain text as the alternative for programs that cannot paste HTML.
+pub fn copy_as_html(state: &mut AppState) {
+    let (text, spans, options) = active_html_source(state);
+    let html = render_html_fragment(&text, &spans, &options);
+    let clipboard = match state.clipboard.take() {
+        Some(clipboard) => Ok(clipboard),
+        None => arboard::Clipboard::new(),
+    };
+    let result = clipboard.and_then(|mut clipboard| {
+        let result = clipboard.set_html(html, Some(text));
+        state.clipboard = Some(clipboard);
+        result
+    });
+    if let Err(e) = result {
+        let message = format!("{}: {}", state.text("error.clipboard"), e);
+        state.report_error(message);
+    }
+}
+
+/// Discards the unsaved changes of the active document by reading its file again.
+pub fn revert_to_saved(state: &mut AppState) {
+    let document = state.notepad_state.active_mut();
+    let encoding = document.encoding.encoding;
+
+    match reload_from_disk(document, encoding) {
+        Ok(()) => info!("Reverted {:?}", document.current_file_path),
+        Err(e) => {
+            let path = document.current_file_path.clone().unwrap_or_default();
+            report_open_error(state, &path, &e);
+        }
+    }
+}
+
+fn mark_saved(document: &mut Document, disk_state: DiskState) {
+    document.file_content = Some(document.current_content.clone());
+    document.disk_state = Some(disk_state);
+    document.file_line_ending = Some(document.line_ending);
+    document.mixed_line_endings = false;
+    document.line_endings_unified = false;
+}
+
+enum WriteError {
+    Unmappable(Unmappable),
+    Io(io::Error),
+}
+
+fn write_document(
+    path: &Path,
+    document: &Document,
+    encoding: &TextEncoding,
+    backups: usize,
+) -> Result<DiskState, WriteError> {
+    let content = line_ending::apply(&document.current_content, document.line_ending);
+    let bytes = encode(&content, encoding).map_err(WriteError::Unmappable)?;
+    write_atomic(path, &bytes, backups).map_err(WriteError::Io)?;
+    Ok(DiskState::new(path, &bytes))
+}
+
+fn save_error_message(state: &AppState, path: &Path, error: WriteError) -> String {
+    let reason = match error {
+        WriteError::Unmappable(unmappable) => unmappable_message(state, &unmappable),
+        WriteError::Io(e) => e.to_string(),
+    };
+    format!("{} {}: {}", state.text("error.save.file"), path.display(), reason)
+}
+
+fn unmappable_message(state: &AppState, unmappable: &Unmappable) -> String {
+    state
+        .text("error.encoding.unmappable")
+        .replace("{char}", &unmappable.char.to_string())
+        .replace("{encoding}", unmappable.encoding.name())
+}
+
+pub fn close_document(state: &mut AppState, index: usize) {
+    if index >= state.notepad_state.documents.len() {
+        return;
+    }
+
+    let document = state.notepad_state.documents.remove(index);
+    discard_recovery(state, &document);
+
+    let notepad_state = &mut state.notepad_state;
+
+    if notepad_state.documents.is_empty() {
+        notepad_state.documents.push(Document::with_settings(&state.settings_state.current));
+    }
+
+    if notepad_state.active_document > index
+        || notepad_state.active_document >= notepad_state.documents.len()
+    {
+        notepad_state.active_document = notepad_state.active_document.saturating_sub(1);
+    }
+}
+
+pub fn undo(state: &mut AppState) {
+    let undo_limit = state.settings_state.current.undo_limit;
+    state.notepad_state.active_mut().undo(undo_limit);
+}
+
+pub fn redo(state: &mut AppState) {
+    let undo_limit = state.settings_state.current.undo_limit;
+    state.notepad_state.active_mut().redo(undo_limit);
+}
+
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 4.0;
+const ZOOM_STEP: f32 = 0.1;
+
+/// Kept unrounded, so the small factors of a Ctrl+wheel or pinch add up; the status bar
+/// shows it in whole percents.
+pub fn set_zoom(state: &mut AppState, zoom: f32) {
+    state.notepad_state.zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+}
+
+pub fn zoom_in(state: &mut AppState) {
+    set_zoom(state, state.notepad_state.zoom + ZOOM_STEP);
+}
+
+pub fn zoom_out(state: &mut AppState) {
+    set_zoom(state, state.notepad_state.zoom - ZOOM_STEP);
+}
+
+pub fn open_find(state: &mut AppState, show_replace: bool) {
+    let document = state.notepad_state.active();
+    let selection = document.selection();
+    let selected: String = document
+        .current_content
+        .chars()
+        .skip(selection.start)
+        .take(selection.len())
+        .collect();
+
+    let find_replace = &mut state.notepad_state.find_replace;
+    if !selected.is_empty() && !selected.contains('\n') {
+        find_replace.query = selected;
+    }
+    find_replace.visible = true;
+    find_replace.show_replace = show_replace;
+    find_replace.focus_query = true;
+}
+
+pub fn close_find(state: &mut AppState) {
+    let find_replace = &mut state.notepad_state.find_replace;
+    find_replace.visible = false;
+    find_replace.matches.clear();
+    find_replace.current_match = None;
+    find_replace.error = None;
+    find_replace.search_key = None;
+}
+
+/// Re-runs the search when the query, its options or the active document changed.
+pub fn refresh_matches(state: &mut AppState) {
+    let NotepadState { documents, active_document, find_replace, .. } = &mut state.notepad_state;
+    let document = &documents[*active_document];
+
+    let search_key = SearchKey {
+        document_id: document.id,
+        revision: document.revision,
+        query: find_replace.query.clone(),
+        options: find_replace.options.clone(),
+    };
+
+    if find_replace.search_key.as_ref() == Some(&search_key) {
+        return;
+    }
+
+    find_replace.search_key = Some(search_key);
+    find_replace.matches.clear();
+    find_replace.current_match = None;
+    find_replace.error = None;
+
+    if find_replace.query.is_empty() {
+        return;
+    }
+
+    match build_regex(&find_replace.query, &find_replace.options) {
+        Ok(regex) => {
+            find_replace.matches = find_matches(&document.current_content, &regex);
+
+            let selection = document.selection();
+            let selection = byte_index(&document.current_content, selection.start)
+                ..byte_index(&document.current_content, selection.end);
+            find_replace.current_match = find_replace
+                .matches
+                .iter()
+                .position(|range| *range == selection);
+        }
+        Err(e) => find_replace.error = Some(e.to_string()),
+    }
+}
+
+pub fn find_next(state: &mut AppState) {
+    refresh_matches(state);
+
+    let document = state.notepad_state.active();
+    let selection_end = byte_index(&document.current_content, document.selection().end);
+    let matches = &state.notepad_state.find_replace.matches;
+
+    if !matches.is_empty() {
+        let index = matches
+            .iter()
+            .position(|range| range.start >= selection_end)
+            .unwrap_or(0);
+        select_match(state, index);
+    }
+}
+
+pub fn find_previous(state: &mut AppState) {
+    refresh_matches(state);
+
+    let document = state.notepad_state.active();
+    let selection_start = byte_index(&document.current_content, document.selection().start);
+    let matches = &state.notepad_state.find_replace.matches;
+
+    if !matches.is_empty() {
+        let index = matches
+            .iter()
+            .rposition(|range| range.start < selection_start)
+            .unwrap_or(matches.len() - 1);
+        select_match(state, index);
+    }
+}
+
+fn select_match(state: &mut AppState, index: usize) {
+    let NotepadState { documents, active_document, find_replace, .. } = &mut state.notepad_state;
+    let document = &mut documents[*active_document];
+    let range = &find_replace.matches[index];
+
+    find_replace.current_match = Some(index);
+    document.pending_selection = Some(
+        char_index(&document.current_content, range.start)
+            ..char_index(&document.current_content, range.end),
+    );
+}
+
+pub fn replace_current(state: &mut AppState) {
+    if state.notepad_state.active().read_only {
+        return;
+    }
+
+    refresh_matches(state);
+
+    let undo_limit = state.settings_state.current.undo_limit;
+    let NotepadState { documents, active_document, find_replace, .. } = &mut state.notepad_state;
+    let document = &mut documents[*active_document];
+
+    let selection = document.selection();
+    let selection = byte_index(&document.current_content, selection.start)
+        ..byte_index(&document.current_content, selection.end);
+
+    if !find_replace.matches.contains(&selection) {
+        find_next(state);
+        return;
+    }
+
+    let Ok(regex) = build_regex(&find_replace.query, &find_replace.options) else {
+        return;
+    };
+
+    let old_len = document.current_content.len();
+    let content = replace_match(
+        &document.current_content,
+        &regex,
+        &selection,
+        &find_replace.replacement,
+        &find_replace.options,
+    );
+    let replaced_end = content.len() + selection.end - old_len;
+    document.replace_content(content, undo_limit);
+
+    let cursor = char_index(&document.current_content, replaced_end);
+    document.cursor = Some(egui::text::CCursorRange::one(egui::text::CCursor::new(cursor)));
+    document.pending_selection = Some(cursor..cursor);
+    find_next(state);
+}
+
+pub fn replace_all_matches(state: &mut AppState) {
+    if state.notepad_state.active().read_only {
+        return;
+    }
+
+    let undo_limit = state.settings_state.current.undo_limit;
+    let NotepadState { documents, active_document, find_replace, .. } = &mut state.notepad_state;
+    let document = &mut documents[*active_document];
+
+    if find_replace.query.is_empty() {
+        return;
+    }
+
+    let Ok(regex) = build_regex(&find_replace.query, &find_replace.options) else {
+        return;
+    };
+
+    let (content, count) = replace_all(
+        &document.current_content,
+        &regex,
+        &find_replace.replacement,
+        &find_replace.options,
+    );
+
+    if count > 0 {
+        info!("Replaced {} matches", count);
+        document.replace_content(content, undo_limit);
+    }
+}
+
+pub fn open_encoding_dialog(state: &mut AppState, mode: EncodingDialogMode) {
+    let encoding = state.notepad_state.active().encoding;
+    state.notepad_state.encoding_dialog = Some(EncodingDialog {
+        mode,
+        encoding,
+        error: None,
+    });
+}
+
+pub fn convert_line_endings(state: &mut AppState, line_ending: LineEnding) {
+    let undo_limit = state.settings_state.current.undo_limit;
+    let document = state.notepad_state.active_mut();
+    if document.read_only {
+        return;
+    }
+
+    let before = document.line_endings();
+    let after = LineEndings {
+        line_ending,
+        mixed: false,
+        unified: before.unified || before.mixed,
+    };
+    document.history.record_line_endings(before, after, undo_limit);
+    document.set_line_endings(after);
+}
@@ -0,0 +1,243 @@
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+
+/// Encodings offered in the "Reopen/Save with encoding" dialogs and in settings.
+pub const AVAILABLE_ENCODINGS: &[&Encoding] = &[
+    UTF_8,
+    UTF_16LE,
+    UTF_16BE,
+    encoding_rs::WINDOWS_1252,
+    encoding_rs::ISO_8859_15,
+    encoding_rs::ISO_8859_2,
+    encoding_rs::WINDOWS_1250,
+    encoding_rs::WINDOWS_1251,
+    encoding_rs::KOI8_R,
+    encoding_rs::SHIFT_JIS,
+    encoding_rs::EUC_JP,
+    encoding_rs::EUC_KR,
+    encoding_rs::GBK,
+    encoding_rs::BIG5,
+];
+
+/// How a document is stored on disk.
+#[derive(Clone, Copy, PartialEq)]
+pub struct TextEncoding {
+    pub encoding: &'static Encoding,
+    pub bom: bool,
+}
+
+impl Default for TextEncoding {
+    fn default() -> Self {
+        Self {
+            encoding: UTF_8,
+            bom: false,
+        }
+    }
+}
+
+impl TextEncoding {
+    pub fn from_name(name: &str) -> Self {
+        Self {
+            encoding: Encoding::for_label(name.as_bytes()).unwrap_or(UTF_8),
+            bom: false,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        if self.bom {
+            format!("{} BOM", self.encoding.name())
+        } else {
+            self.encoding.name().to_string()
+        }
+    }
+}
+
+/// Only the Unicode encodings can carry a byte order mark.
+pub fn supports_bom(encoding: &'static Encoding) -> bool {
+    encoding == UTF_8 || encoding == UTF_16LE || encoding == UTF_16BE
+}
+
+/// Decodes a file, detecting its encoding from the BOM or, failing that, from its content.
+pub fn decode(bytes: &[u8]) -> (String, TextEncoding) {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_length..]);
+        return (text.into_owned(), TextEncoding { encoding, bom: true });
+    }
+
+    let encoding = detect_encoding(bytes);
+    let (text, _) = encoding.decode_without_bom_handling(bytes);
+    (text.into_owned(), TextEncoding { encoding, bom: false })
+}
+
+/// Decodes a file with an encoding chosen by the user. Returns `true` if some bytes were invalid.
+pub fn decode_with(bytes: &[u8], encoding: &'static Encoding) -> (String, TextEncoding, bool) {
+    let bom = matches!(Encoding::for_bom(bytes), Some((found, _)) if found == encoding);
+    let bom_length = if bom { bom_length(encoding) } else { 0 };
+
+    let (text, had_errors) = encoding.decode_without_bom_handling(&bytes[bom_length..]);
+    (text.into_owned(), TextEncoding { encoding, bom }, had_errors)
+}
+
+/// A character the target encoding has no bytes for.
+#[derive(Debug)]
+pub struct Unmappable {
+    pub char: char,
+    pub encoding: &'static Encoding,
+}
+
+/// Encodes the text, failing if a character can't be represented in the target encoding.
+pub fn encode(text: &str, target: &TextEncoding) -> Result<Vec<u8>, Unmappable> {
+    let bom = target.bom && supports_bom(target.encoding);
+    let mut bytes = Vec::with_capacity(text.len() + 3);
+
+    if target.encoding == UTF_16LE || target.encoding == UTF_16BE {
+        let little_endian = target.encoding == UTF_16LE;
+        if bom {
+            bytes.extend_from_slice(if little_endian { &[0xFF, 0xFE] } else { &[0xFE, 0xFF] });
+        }
+        for unit in text.encode_utf16() {
+            let unit_bytes = if little_endian { unit.to_le_bytes() } else { unit.to_be_bytes() };
+            bytes.extend_from_slice(&unit_bytes);
+        }
+        return Ok(bytes);
+    }
+
+    if bom {
+        bytes.extend_from_slice(&[0xEF, 0xBB, 0xBF]);
+    }
+
+    let (encoded, _, had_errors) = target.encoding.encode(text);
+    if had_errors {
+        let unmappable = text
+            .chars()
+            .find(|c| target.encoding.encode(c.encode_utf8(&mut [0; 4])).2)
+            .unwrap_or('?');
+        return Err(Unmappable {
+            char: unmappable,
+            encoding: target.encoding,
+        });
+    }
+
+    bytes.extend_from_slice(&encoded);
+    Ok(bytes)
+}
+
+fn bom_length(encoding: &'static Encoding) -> usize {
+    if encoding == UTF_8 { 3 } else { 2 }
+}
+
+fn detect_encoding(bytes: &[u8]) -> &'static Encoding {
+    // Checked first: ASCII text in UTF-16 is also valid UTF-8, just full of NULs.
+    if let Some(encoding) = detect_utf16_without_bom(bytes) {
+        return encoding;
+    }
+
+    if std::str::from_utf8(bytes).is_ok() {
+        return UTF_8;
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    detector.guess(None, true)
+}
+
+/// Mostly-ASCII UTF-16 text has a zero in every other byte.
+fn detect_utf16_without_bom(bytes: &[u8]) -> Option<&'static Encoding> {
+    let sample = &bytes[..bytes.len().min(4096) & !1];
+    if sample.len() < 4 {
+        return None;
+    }
+
+    let pairs = sample.len() / 2;
+    let even_zeros = sample.iter().step_by(2).filter(|byte| **byte == 0).count();
+    let odd_zeros = sample.iter().skip(1).step_by(2).filter(|byte| **byte == 0).count();
+
+    if odd_zeros * 10 > pairs * 4 && even_zeros * 10 < pairs {
+        Some(UTF_16LE)
+    } else if even_zeros * 10 > pairs * 4 && odd_zeros * 10 < pairs {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoding(encoding: &'static Encoding, bom: bool) -> TextEncoding {
+        TextEncoding { encoding, bom }
+    }
+
+    #[test]
+    fn utf8_without_bom_is_detected() {
+        let (text, found) = decode("héllo".as_bytes());
+        assert_eq!(text, "héllo");
+        assert!(found == encoding(UTF_8, false));
+    }
+
+    #[test]
+    fn boms_are_detected_and_stripped() {
+        let (text, found) = decode(b"\xEF\xBB\xBFhi");
+        assert_eq!(text, "hi");
+        assert!(found == encoding(UTF_8, true));
+
+        let (text, found) = decode(b"\xFF\xFEh\0i\0");
+        assert_eq!(text, "hi");
+        assert!(found == encoding(UTF_16LE, true));
+
+        let (text, found) = decode(b"\xFE\xFF\0h\0i");
+        assert_eq!(text, "hi");
+        assert!(found == encoding(UTF_16BE, true));
+    }
+
+    #[test]
+    fn utf16_without_bom_is_detected() {
+        for target in [UTF_16LE, UTF_16BE] {
+            let bytes = encode("plain ascii text", &encoding(target, false)).unwrap();
+            let (text, found) = decode(&bytes);
+            assert_eq!(text, "plain ascii text");
+            assert!(found == encoding(target, false));
+        }
+    }
+
+    #[test]
+    fn legacy_bytes_fall_back_to_a_single_byte_encoding() {
+        let (text, found) = decode(b"caf\xE9 cr\xE8me br\xFBl\xE9e");
+        assert_eq!(text, "café crème brûlée");
+        assert!(found.encoding != UTF_8 && !found.bom);
+    }
+
+    #[test]
+    fn encode_round_trips_with_and_without_bom() {
+        for target in [UTF_8, UTF_16LE, UTF_16BE, encoding_rs::WINDOWS_1252] {
+            for bom in [false, true] {
+                let bytes = encode("grüße", &encoding(target, bom)).unwrap();
+                let (text, found, had_errors) = decode_with(&bytes, target);
+                assert_eq!(text, "grüße");
+                assert!(!had_errors);
+                assert_eq!(found.bom, bom && supports_bom(target));
+            }
+        }
+    }
+
+    #[test]
+    fn unmappable_characters_are_an_error() {
+        let error = encode("price: €5 ✓", &encoding(encoding_rs::ISO_8859_2, false)).unwrap_err();
+        assert_eq!(error.char, '€');
+        assert!(error.encoding == encoding_rs::ISO_8859_2);
+    }
+
+    #[test]
+    fn decode_with_reports_invalid_bytes() {
+        let (_, _, had_errors) = decode_with(b"\xFF\xFE\xFD", UTF_8);
+        assert!(had_errors);
+    }
+
+    #[test]
+    fn labels_and_names() {
+        assert_eq!(encoding(UTF_8, true).label(), "UTF-8 BOM");
+        assert!(TextEncoding::from_name("latin1").encoding == encoding_rs::WINDOWS_1252);
+        assert!(TextEncoding::from_name("not an encoding").encoding == UTF_8);
+    }
+}
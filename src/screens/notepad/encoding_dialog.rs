@@ -0,0 +1,98 @@
+use crate::app_state::{AppState, EncodingDialog, EncodingDialogMode};
+use crate::screens::notepad::commands::{reopen_with_encoding, save_with_encoding};
+use crate::screens::notepad::encoding::{supports_bom, AVAILABLE_ENCODINGS};
+use eframe::egui;
+
+pub fn show_encoding_dialog(ctx: &egui::Context, state: &mut AppState) {
+    let Some(dialog) = &state.notepad_state.encoding_dialog else {
+        return;
+    };
+
+    let (title_key, button_key) = match dialog.mode {
+        EncodingDialogMode::Reopen => (
+            "notepad.encoding.dialog.reopen.title",
+            "notepad.encoding.dialog.button.reopen",
+        ),
+        EncodingDialogMode::Save => (
+            "notepad.encoding.dialog.save.title",
+            "notepad.encoding.dialog.button.save",
+        ),
+    };
+    let title = state.text(title_key);
+    let confirm_text = state.text(button_key);
+    let cancel_text = state.text("notepad.encoding.dialog.button.cancel");
+    let bom_text = state.text("notepad.encoding.dialog.bom");
+    let discard_warning = state
+        .notepad_state
+        .active()
+        .is_dirty()
+        .then(|| state.text("notepad.encoding.dialog.reopen.warning"));
+
+    let mut confirmed = false;
+    let mut cancelled = false;
+
+    egui::Window::new(title)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            let Some(dialog) = &mut state.notepad_state.encoding_dialog else {
+                return;
+            };
+
+            egui::ComboBox::from_id_salt("Encoding")
+                .selected_text(dialog.encoding.encoding.name())
+                .show_ui(ui, |ui| {
+                    for encoding in AVAILABLE_ENCODINGS {
+                        ui.selectable_value(&mut dialog.encoding.encoding, *encoding, encoding.name());
+                    }
+                });
+
+            match dialog.mode {
+                EncodingDialogMode::Save => {
+                    if supports_bom(dialog.encoding.encoding) {
+                        ui.checkbox(&mut dialog.encoding.bom, bom_text);
+                    } else {
+                        dialog.encoding.bom = false;
+                    }
+                }
+                EncodingDialogMode::Reopen => {
+                    if let Some(warning) = &discard_warning {
+                        ui.colored_label(ui.visuals().warn_fg_color, warning);
+                    }
+                }
+            }
+
+            if let Some(error) = &dialog.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+
+            ui.horizontal(|ui| {
+                if ui.button(&cancel_text).clicked() {
+                    cancelled = true;
+                }
+
+                if ui.button(&confirm_text).clicked() {
+                    confirmed = true;
+                }
+            });
+        });
+
+    if cancelled {
+        state.notepad_state.encoding_dialog = None;
+    } else if confirmed && let Some(dialog) = state.notepad_state.encoding_dialog.take() {
+        let result = match dialog.mode {
+            EncodingDialogMode::Reopen => {
+                reopen_with_encoding(state, dialog.encoding.encoding).map_err(|e| e.to_string())
+            }
+            EncodingDialogMode::Save => save_with_encoding(state, dialog.encoding),
+        };
+
+        if let Err(error) = result {
+            state.notepad_state.encoding_dialog = Some(EncodingDialog {
+                error: Some(error),
+                ..dialog
+            });
+        }
+    }
+}
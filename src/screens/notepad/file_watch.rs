@@ -0,0 +1,245 @@
+use crate::app_state::{AppState, Document, ExternalChangePrompt};
+use crate::screens::notepad::commands::{
+    close_document, reload_from_disk, report_open_error, save_with_encoding,
+};
+use crate::screens::notepad::encoding::TextEncoding;
+use eframe::egui;
+use log::info;
+use std::fs;
+use std::io;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+const CHECK_INTERVAL_SECS: f64 = 2.0;
+
+/// What the file looked like on disk the last time it was read or written.
+#[derive(Clone, Copy, PartialEq)]
+pub struct DiskState {
+    modified: Option<SystemTime>,
+    len: u64,
+    hash: u64,
+}
+
+impl DiskState {
+    pub fn new(path: &Path, bytes: &[u8]) -> Self {
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+
+        Self {
+            modified: fs::metadata(path).and_then(|metadata| metadata.modified()).ok(),
+            len: bytes.len() as u64,
+            hash: hasher.finish(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExternalChange {
+    Modified,
+    Deleted,
+}
+
+/// Compares the file on disk with the state recorded for the document.
+///
+/// Only reads the file when its size or modification time changed; a touch that
+/// leaves the content identical just refreshes the recorded state. A missing file is
+/// reported as deleted, any other failure to read it as an error.
+fn check_document(document: &mut Document) -> io::Result<Option<ExternalChange>> {
+    let (Some(path), Some(known)) = (&document.current_file_path, document.disk_state) else {
+        return Ok(None);
+    };
+
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Some(ExternalChange::Deleted)),
+        Err(e) => return Err(e),
+    };
+
+    if metadata.len() == known.len && metadata.modified().ok() == known.modified {
+        return Ok(None);
+    }
+
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Some(ExternalChange::Deleted)),
+        Err(e) => return Err(e),
+    };
+
+    let current = DiskState::new(path, &bytes);
+    if current.hash == known.hash {
+        document.disk_state = Some(current);
+        Ok(None)
+    } else {
+        Ok(Some(ExternalChange::Modified))
+    }
+}
+
+/// Looks for files changed by other programs every couple of seconds.
+pub fn check_external_changes_if_due(state: &mut AppState, ctx: &egui::Context) {
+    let now = ctx.input(|i| i.time);
+    ctx.request_repaint_after(Duration::from_secs_f64(CHECK_INTERVAL_SECS));
+
+    if state.notepad_state.external_change_prompt.is_some()
+        || now - state.notepad_state.last_external_check < CHECK_INTERVAL_SECS
+    {
+        return;
+    }
+    state.notepad_state.last_external_check = now;
+
+    check_external_changes(state);
+}
+
+pub fn check_external_changes(state: &mut AppState) {
+    let auto_reload = state.settings_state.current.auto_reload_unmodified;
+
+    for index in 0..state.notepad_state.documents.len() {
+        let document = &mut state.notepad_state.documents[index];
+        let change = match check_document(document) {
+            Ok(Some(change)) => change,
+            Ok(None) => continue,
+            Err(e) => {
+                // Stop watching until the file is saved or reloaded, so this is reported once.
+                document.disk_state = None;
+                let path = document.current_file_path.clone().unwrap_or_default();
+                let message = format!("{} {}: {}", state.text("error.watch.file"), path.display(), e);
+                state.report_error(message);
+                return;
+            }
+        };
+
+        if change == ExternalChange::Modified && auto_reload && !document.is_dirty() {
+            let encoding = document.encoding.encoding;
+            match reload_from_disk(document, encoding) {
+                Ok(()) => info!("Reloaded {:?} after an external change", document.current_file_path),
+                Err(e) => info!("Could not reload {:?} -> {}", document.current_file_path, e),
+            }
+            continue;
+        }
+
+        state.notepad_state.active_document = index;
+        state.notepad_state.external_change_prompt = Some(ExternalChangePrompt {
+            document_id: document.id,
+            change,
+            pending_save: None,
+        });
+        return;
+    }
+}
+
+/// Returns `true` if the active document's file changed on disk since it was loaded,
+/// in which case the user is asked before anything gets overwritten. The save in
+/// `encoding` is carried out if they keep their version.
+pub fn confirm_overwrite_needed(state: &mut AppState, encoding: TextEncoding) -> bool {
+    let document = state.notepad_state.active_mut();
+    // A file that can't be read is left to the write, which reports why it failed.
+    let Ok(Some(change)) = check_document(document) else {
+        return false;
+    };
+
+    if change == ExternalChange::Deleted {
+        return false;
+    }
+
+    let document_id = document.id;
+    state.notepad_state.external_change_prompt = Some(ExternalChangePrompt {
+        document_id,
+        change,
+        pending_save: Some(encoding),
+    });
+    true
+}
+
+pub fn show_external_change_dialog(ctx: &egui::Context, state: &mut AppState) {
+    let Some(prompt) = &state.notepad_state.external_change_prompt else {
+        return;
+    };
+
+    let Some(index) = state
+        .notepad_state
+        .documents
+        .iter()
+        .position(|document| document.id == prompt.document_id)
+    else {
+        state.notepad_state.external_change_prompt = None;
+        return;
+    };
+
+    let change = prompt.change;
+    let pending_save = prompt.pending_save;
+    let untitled = state.text("notepad.tab.untitled");
+    let name = state.notepad_state.documents[index].title(&untitled);
+    let (title_key, message_key, keep_key, other_key) = match change {
+        ExternalChange::Modified => (
+            "notepad.external.modified.title",
+            "notepad.external.modified.message",
+            "notepad.external.button.keep",
+            "notepad.external.button.reload",
+        ),
+        ExternalChange::Deleted => (
+            "notepad.external.deleted.title",
+            "notepad.external.deleted.message",
+            "notepad.external.button.keep",
+            "notepad.external.button.close",
+        ),
+    };
+
+    let mut keep = false;
+    let mut other = false;
+
+    egui::Window::new(state.text(title_key))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            ui.label(state.text(message_key).replace("{file}", &name));
+
+            ui.horizontal(|ui| {
+                if ui.button(state.text(keep_key)).clicked() {
+                    keep = true;
+                }
+
+                if ui.button(state.text(other_key)).clicked() {
+                    other = true;
+                }
+            });
+        });
+
+    if !keep && !other {
+        return;
+    }
+
+    state.notepad_state.external_change_prompt = None;
+    let document = &mut state.notepad_state.documents[index];
+
+    match (change, keep) {
+        (ExternalChange::Modified, true) => {
+            // Accept the new disk state so the next save overwrites it deliberately.
+            if let Some(path) = &document.current_file_path
+                && let Ok(bytes) = fs::read(path)
+            {
+                document.disk_state = Some(DiskState::new(path, &bytes));
+            }
+
+            if let Some(encoding) = pending_save {
+                state.notepad_state.active_document = index;
+                if let Err(message) = save_with_encoding(state, encoding) {
+                    state.report_error(message);
+                }
+            }
+        }
+        (ExternalChange::Modified, false) => {
+            let encoding = document.encoding.encoding;
+            if let Err(e) = reload_from_disk(document, encoding) {
+                let path = document.current_file_path.clone().unwrap_or_default();
+                report_open_error(state, &path, &e);
+            }
+        }
+        (ExternalChange::Deleted, true) => {
+            // Nothing on disk matches the buffer any more, so it counts as unsaved.
+            document.disk_state = None;
+            document.file_content = None;
+        }
+        (ExternalChange::Deleted, false) => close_document(state, index),
+    }
+}
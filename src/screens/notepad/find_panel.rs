@@ -0,0 +1,109 @@
+use crate::app_state::AppState;
+use crate::screens::notepad::commands::{
+    close_find, find_next, find_previous, refresh_matches, replace_all_matches, replace_current,
+};
+use eframe::egui;
+
+pub fn app_find_panel(state: &mut AppState, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    if !state.notepad_state.find_replace.visible {
+        return;
+    }
+
+    refresh_matches(state);
+
+    egui::TopBottomPanel::top("find_panel").show(ctx, |ui| {
+        ui.add_enabled_ui(!state.notepad_state.show_save_modal, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(state.text("notepad.find.label"));
+
+                let query_response = ui.add(
+                    egui::TextEdit::singleline(&mut state.notepad_state.find_replace.query)
+                        .desired_width(240.0),
+                );
+                if state.notepad_state.find_replace.focus_query {
+                    query_response.request_focus();
+                    state.notepad_state.find_replace.focus_query = false;
+                }
+                if query_response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    if ui.input(|i| i.modifiers.shift) {
+                        find_previous(state);
+                    } else {
+                        find_next(state);
+                    }
+                    query_response.request_focus();
+                }
+
+                if ui
+                    .button("⏶")
+                    .on_hover_text(state.text("notepad.find.previous"))
+                    .clicked()
+                {
+                    find_previous(state);
+                }
+
+                if ui
+                    .button("⏷")
+                    .on_hover_text(state.text("notepad.find.next"))
+                    .clicked()
+                {
+                    find_next(state);
+                }
+
+                ui.label(match_counter(state));
+
+                ui.separator();
+
+                let match_case_text = state.text("notepad.find.match.case");
+                let whole_word_text = state.text("notepad.find.whole.word");
+                let regex_text = state.text("notepad.find.regex");
+                let options = &mut state.notepad_state.find_replace.options;
+                ui.checkbox(&mut options.match_case, match_case_text);
+                ui.checkbox(&mut options.whole_word, whole_word_text);
+                ui.checkbox(&mut options.use_regex, regex_text);
+
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.small_button("×").clicked() {
+                        close_find(state);
+                    }
+                });
+            });
+
+            if state.notepad_state.find_replace.show_replace {
+                ui.horizontal(|ui| {
+                    ui.label(state.text("notepad.replace.label"));
+
+                    ui.add(
+                        egui::TextEdit::singleline(&mut state.notepad_state.find_replace.replacement)
+                            .desired_width(240.0),
+                    );
+
+                    if ui.button(state.text("notepad.replace.button")).clicked() {
+                        replace_current(state);
+                    }
+
+                    if ui.button(state.text("notepad.replace.all.button")).clicked() {
+                        replace_all_matches(state);
+                    }
+                });
+            }
+
+            if let Some(error) = &state.notepad_state.find_replace.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+        });
+    });
+}
+
+fn match_counter(state: &AppState) -> String {
+    let find_replace = &state.notepad_state.find_replace;
+
+    match find_replace.current_match {
+        Some(index) => state
+            .text("notepad.find.counter")
+            .replace("{current}", &(index + 1).to_string())
+            .replace("{total}", &find_replace.matches.len().to_string()),
+        None => state
+            .text("notepad.find.total")
+            .replace("{total}", &find_replace.matches.len().to_string()),
+    }
+}
@@ -0,0 +1,145 @@
+use crate::app_state::AppState;
+use crate::screens::notepad::commands::go_to_position;
+use crate::screens::notepad::search::line_column;
+use eframe::egui;
+
+/// Parses `line`, `line:column`, `+N` or `-N` (relative to `current_line`, optionally with
+/// `:column`) into a 1-based line and column.
+fn parse_target(input: &str, current_line: usize, line_count: usize) -> Option<(usize, usize)> {
+    let input = input.trim();
+
+    let (line_text, column_text) = match input.split_once(':') {
+        Some((line, column)) => (line.trim(), Some(column.trim())),
+        None => (input, None),
+    };
+
+    let line = if let Some(offset) = line_text.strip_prefix('+') {
+        offset.parse::<usize>().ok().map(|offset| current_line.saturating_add(offset))
+    } else if let Some(offset) = line_text.strip_prefix('-') {
+        offset.parse::<usize>().ok().map(|offset| current_line.saturating_sub(offset))
+    } else {
+        line_text.parse::<usize>().ok()
+    };
+    let column = match column_text {
+        Some(column) => column.parse::<usize>().ok().filter(|column| *column > 0),
+        None => Some(1),
+    };
+
+    match (line, column) {
+        (Some(line), Some(column)) if (1..=line_count).contains(&line) => Some((line, column)),
+        _ => None,
+    }
+}
+
+pub fn show_go_to_line_dialog(ctx: &egui::Context, state: &mut AppState) {
+    if state.notepad_state.go_to_line_dialog.is_none() {
+        return;
+    }
+
+    let document = state.notepad_state.active();
+    let cursor = document.cursor.map_or(0, |range| range.primary.index);
+    let (current_line, _) = line_column(&document.current_content, cursor);
+    let line_count = document.current_content.matches('\n').count() + 1;
+
+    let title = state.text("notepad.goto.dialog.title");
+    let hint = state
+        .text("notepad.goto.dialog.hint")
+        .replace("{count}", &line_count.to_string());
+    let invalid_text = state
+        .text("notepad.goto.dialog.invalid")
+        .replace("{count}", &line_count.to_string());
+    let cancel_text = state.text("notepad.goto.dialog.button.cancel");
+    let confirm_text = state.text("notepad.goto.dialog.button.go");
+
+    let mut confirmed = false;
+    let mut cancelled = false;
+
+    egui::Window::new(title)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            let Some(dialog) = &mut state.notepad_state.go_to_line_dialog else {
+                return;
+            };
+
+            ui.label(&hint);
+            let response = ui.add(egui::TextEdit::singleline(&mut dialog.input).hint_text(format!("{current_line}")));
+            if dialog.focus_input {
+                response.request_focus();
+                dialog.focus_input = false;
+            }
+            if response.changed() {
+                dialog.error = None;
+            }
+
+            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                confirmed = true;
+            }
+            if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                cancelled = true;
+            }
+
+            if let Some(error) = &dialog.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+
+            ui.horizontal(|ui| {
+                if ui.button(&cancel_text).clicked() {
+                    cancelled = true;
+                }
+
+                if ui.button(&confirm_text).clicked() {
+                    confirmed = true;
+                }
+            });
+        });
+
+    if cancelled {
+        state.notepad_state.go_to_line_dialog = None;
+    } else if confirmed && let Some(dialog) = &mut state.notepad_state.go_to_line_dialog {
+        match parse_target(&dialog.input, current_line, line_count) {
+            Some((line, column)) => {
+                state.notepad_state.go_to_line_dialog = None;
+                go_to_position(state, line, column);
+            }
+            None => {
+                dialog.error = Some(invalid_text);
+                dialog.focus_input = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn absolute_lines_and_columns() {
+        assert_eq!(parse_target("12", 1, 100), Some((12, 1)));
+        assert_eq!(parse_target(" 12 : 5 ", 1, 100), Some((12, 5)));
+    }
+
+    #[test]
+    fn relative_lines() {
+        assert_eq!(parse_target("+5", 10, 100), Some((15, 1)));
+        assert_eq!(parse_target("-3:2", 10, 100), Some((7, 2)));
+    }
+
+    #[test]
+    fn lines_outside_the_document_are_rejected() {
+        assert_eq!(parse_target("0", 1, 100), None);
+        assert_eq!(parse_target("101", 1, 100), None);
+        assert_eq!(parse_target("-20", 10, 100), None);
+        assert_eq!(parse_target("+95", 10, 100), None);
+    }
+
+    #[test]
+    fn malformed_input_is_rejected() {
+        assert_eq!(parse_target("", 1, 100), None);
+        assert_eq!(parse_target("abc", 1, 100), None);
+        assert_eq!(parse_target("3:0", 1, 100), None);
+        assert_eq!(parse_target("3:x", 1, 100), None);
+    }
+}
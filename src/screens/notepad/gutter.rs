@@ -0,0 +1,111 @@
+use eframe::egui;
+use eframe::egui::text_edit::TextEditOutput;
+
+const PADDING: f32 = 8.0;
+
+/// Width needed to show the largest line number.
+pub fn gutter_width(ui: &egui::Ui, line_count: usize) -> f32 {
+    let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+    let digit_width = ui.fonts(|fonts| fonts.glyph_width(&font_id, '0'));
+    let digits = line_count.max(1).ilog10() as f32 + 1.0;
+    digits.max(2.0) * digit_width + 2.0 * PADDING
+}
+
+/// Paints line numbers to the left of the editor and the current-line background into
+/// `current_line_shape`, reserved before the editor so it ends up behind the text.
+///
+/// Wrapped rows share the number of the line they belong to, shown on its first row.
+/// Returns the 1-based line whose number was clicked.
+pub fn show_gutter(
+    ui: &egui::Ui,
+    output: &TextEditOutput,
+    width: f32,
+    current_line_shape: egui::layers::ShapeIdx,
+) -> Option<usize> {
+    let clip = ui.clip_rect();
+    let gutter_rect = egui::Rect::from_min_max(clip.left_top(), egui::pos2(clip.left() + width, clip.bottom()));
+    let galley = &output.galley;
+    let origin = output.galley_pos.to_vec2();
+
+    let current_row = output
+        .cursor_range
+        .map(|range| galley.layout_from_cursor(range.primary).row);
+
+    // Rows of the logical line holding the cursor.
+    if let Some(row) = current_row {
+        let rows = &galley.rows;
+        let mut first = row.min(rows.len().saturating_sub(1));
+        while first > 0 && !rows[first - 1].ends_with_newline {
+            first -= 1;
+        }
+        let mut last = first;
+        while last + 1 < rows.len() && !rows[last].ends_with_newline {
+            last += 1;
+        }
+
+        if let (Some(top), Some(bottom)) = (rows.get(first), rows.get(last)) {
+            let rect = egui::Rect::from_x_y_ranges(
+                clip.x_range(),
+                top.rect().top() + origin.y..=bottom.rect().bottom() + origin.y,
+            );
+            ui.painter().set(
+                current_line_shape,
+                egui::Shape::rect_filled(rect, 0.0, ui.visuals().faint_bg_color),
+            );
+        }
+    }
+
+    let painter = ui.painter();
+    painter.rect_filled(gutter_rect, 0.0, ui.visuals().panel_fill);
+    painter.vline(
+        gutter_rect.right() - 0.5,
+        gutter_rect.y_range(),
+        ui.visuals().widgets.noninteractive.bg_stroke,
+    );
+
+    let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+    let number_x = gutter_rect.right() - PADDING;
+    let current_line = current_row.map(|row| line_of_row(galley, row));
+    let mut line = 1;
+    let mut starts_line = true;
+
+    for row in &galley.rows {
+        let rect = row.rect().translate(origin);
+        if starts_line && rect.bottom() >= clip.top() && rect.top() <= clip.bottom() {
+            let color = if current_line == Some(line) {
+                ui.visuals().strong_text_color()
+            } else {
+                ui.visuals().weak_text_color()
+            };
+            painter.text(
+                egui::pos2(number_x, rect.top()),
+                egui::Align2::RIGHT_TOP,
+                line.to_string(),
+                font_id.clone(),
+                color,
+            );
+        }
+
+        if rect.top() > clip.bottom() {
+            break;
+        }
+
+        starts_line = row.ends_with_newline;
+        if starts_line {
+            line += 1;
+        }
+    }
+
+    let response = ui.interact(gutter_rect, ui.id().with("line_gutter"), egui::Sense::click());
+    let pointer = response.interact_pointer_pos().filter(|_| response.clicked())?;
+    let row = galley
+        .rows
+        .iter()
+        .position(|row| row.rect().translate(origin).bottom() >= pointer.y)
+        .unwrap_or(galley.rows.len().saturating_sub(1));
+    Some(line_of_row(galley, row))
+}
+
+fn line_of_row(galley: &egui::Galley, row: usize) -> usize {
+    1 + galley.rows[..row].iter().filter(|row| row.ends_with_newline).count()
+}
@@ -0,0 +1,356 @@
+use crate::screens::notepad::syntax::{highlight_line, LanguageMode, LineState, Span, TokenKind};
+use eframe::egui;
+use eframe::egui::text::{LayoutJob, LayoutSection, TextFormat};
+use eframe::egui::Galley;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Tokens of one line, with spans relative to the line start.
+struct LexedLine {
+    /// Hash of the line text, to find the lines an edit changed.
+    hash: u64,
+    len: usize,
+    start_state: LineState,
+    end_state: LineState,
+    spans: Vec<Span>,
+}
+
+/// Tokens of the last highlighted text, line by line. An edit re-lexes from the first line
+/// it changed until a line starts in the same state as before, and everything after that
+/// is reused. The finished layout is kept too, so frames without edits reuse it as is.
+#[derive(Default)]
+pub struct HighlightCache {
+    language_mode: LanguageMode,
+    /// Document revision and length the spans were built for.
+    revision: Option<(u64, usize)>,
+    lines: Vec<LexedLine>,
+    spans: Vec<Span>,
+    galley: Option<CachedGalley>,
+}
+
+/// Everything the editor layout depends on besides the text.
+#[derive(PartialEq)]
+struct JobKey {
+    revision: (u64, usize),
+    language_mode: LanguageMode,
+    font_id: egui::FontId,
+    color: egui::Color32,
+    dark_mode: bool,
+    current_match: Option<usize>,
+    wrap_width: f32,
+    pixels_per_point: f32,
+}
+
+struct CachedGalley {
+    key: JobKey,
+    matches: Vec<Range<usize>>,
+    galley: Arc<Galley>,
+}
+
+fn line_hash(line: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    line.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl HighlightCache {
+    fn update(&mut self, language_mode: LanguageMode, revision: u64, text: &str) {
+        if language_mode != self.language_mode {
+            *self = HighlightCache {
+                language_mode,
+                ..Default::default()
+            };
+        } else if self.revision == Some((revision, text.len())) {
+            return;
+        }
+
+        let new_lines: Vec<&str> = text.split('\n').collect();
+        let mut lines = std::mem::take(&mut self.lines);
+
+        // Unchanged lines at both ends keep their tokens; the ones after the change only
+        // while they still start in the state they were lexed with.
+        let prefix = new_lines
+            .iter()
+            .zip(&lines)
+            .take_while(|(line, lexed)| line_hash(line) == lexed.hash)
+            .count();
+        let suffix = new_lines[prefix..]
+            .iter()
+            .rev()
+            .zip(lines[prefix..].iter().rev())
+            .take_while(|(line, lexed)| line_hash(line) == lexed.hash)
+            .count();
+
+        let mut tail = lines.split_off(lines.len() - suffix);
+        lines.truncate(prefix);
+        let suffix_start = new_lines.len() - suffix;
+        let mut state = lines.last().map_or(LineState::Normal, |lexed| lexed.end_state);
+
+        for (index, line) in new_lines.iter().enumerate().skip(prefix) {
+            if index >= suffix_start && tail[index - suffix_start].start_state == state {
+                lines.extend(tail.drain(index - suffix_start..));
+                break;
+            }
+
+            let (spans, end_state) = highlight_line(language_mode, line, state);
+            lines.push(LexedLine {
+                hash: line_hash(line),
+                len: line.len(),
+                start_state: state,
+                end_state,
+                spans,
+            });
+            state = end_state;
+        }
+
+        let mut offset = 0;
+        self.spans.clear();
+        for lexed in &lines {
+            self.spans.extend(lexed.spans.iter().map(|span| Span {
+                range: span.range.start + offset..span.range.end + offset,
+                kind: span.kind,
+            }));
+            offset += lexed.len + 1;
+        }
+
+        self.revision = Some((revision, text.len()));
+        self.lines = lines;
+    }
+
+    /// Tokens of the whole `text` at document `revision`, as byte ranges.
+    pub fn spans(&mut self, language_mode: LanguageMode, revision: u64, text: &str) -> &[Span] {
+        self.update(language_mode, revision, text);
+        &self.spans
+    }
+}
+
+pub fn token_color(kind: TokenKind, dark_mode: bool) -> egui::Color32 {
+    let hex = |dark: u32, light: u32| {
+        let rgb = if dark_mode { dark } else { light };
+        egui::Color32::from_rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+    };
+
+    match kind {
+        TokenKind::Keyword => hex(0xC678DD, 0xA626A4),
+        TokenKind::Literal => hex(0x56B6C2, 0x0184BC),
+        TokenKind::String => hex(0x98C379, 0x50A14F),
+        TokenKind::Number => hex(0xD19A66, 0x986801),
+        TokenKind::Comment => hex(0x7F848E, 0x8E8F96),
+        TokenKind::Key => hex(0x61AFEF, 0x4078F2),
+        TokenKind::Variable => hex(0xE5C07B, 0xC18401),
+        TokenKind::Heading => hex(0xE06C75, 0xE45649),
+        TokenKind::Emphasis => hex(0xE5C07B, 0xC18401),
+        TokenKind::Link => hex(0x61AFEF, 0x4078F2),
+    }
+}
+
+fn token_format(kind: TokenKind, plain: &TextFormat, dark_mode: bool) -> TextFormat {
+    let color = token_color(kind, dark_mode);
+    TextFormat {
+        color,
+        italics: matches!(kind, TokenKind::Comment | TokenKind::Emphasis),
+        underline: if kind == TokenKind::Link {
+            egui::Stroke::new(1.0, color)
+        } else {
+            egui::Stroke::NONE
+        },
+        ..plain.clone()
+    }
+}
+
+/// Find results to draw behind the text, with the current one picked out.
+pub struct FindMatches<'a> {
+    pub ranges: &'a [Range<usize>],
+    pub current: Option<usize>,
+}
+
+/// Lays out the editor text with syntax colors and find-match backgrounds, reusing the
+/// previous galley while the document `revision` and everything drawn on top of it are unchanged.
+pub fn highlight(
+    ui: &egui::Ui,
+    cache: &mut HighlightCache,
+    language_mode: LanguageMode,
+    revision: u64,
+    text: &str,
+    matches: FindMatches,
+    wrap_width: f32,
+) -> Arc<Galley> {
+    let FindMatches { ranges: matches, current: current_match } = matches;
+    let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+    let color = ui
+        .visuals()
+        .override_text_color
+        .unwrap_or_else(|| ui.visuals().widgets.inactive.text_color());
+    let key = JobKey {
+        revision: (revision, text.len()),
+        language_mode,
+        font_id,
+        color,
+        dark_mode: ui.visuals().dark_mode,
+        current_match,
+        wrap_width,
+        pixels_per_point: ui.ctx().pixels_per_point(),
+    };
+
+    if let Some(cached) = &cache.galley
+        && cached.key == key
+        && cached.matches == matches
+    {
+        return cached.galley.clone();
+    }
+
+    // Nothing to color, so skip the spans and their copy of the line tokens.
+    let job = if language_mode == LanguageMode::PlainText && matches.is_empty() {
+        LayoutJob::simple(text.to_string(), key.font_id.clone(), color, wrap_width)
+    } else {
+        cache.update(language_mode, revision, text);
+        build_job(ui, &cache.spans, &key, text, matches)
+    };
+
+    let galley = ui.fonts(|fonts| fonts.layout_job(job));
+    cache.galley = Some(CachedGalley {
+        key,
+        matches: matches.to_vec(),
+        galley: galley.clone(),
+    });
+    galley
+}
+
+fn build_job(
+    ui: &egui::Ui,
+    spans: &[Span],
+    key: &JobKey,
+    text: &str,
+    matches: &[Range<usize>],
+) -> LayoutJob {
+    let dark_mode = key.dark_mode;
+    let plain = TextFormat::simple(key.font_id.clone(), key.color);
+    let match_background = ui.visuals().warn_fg_color.gamma_multiply(0.35);
+    let current_background = ui.visuals().selection.bg_fill;
+
+    // Matches can be one frame behind the text while the user is typing.
+    let mut last_end = 0;
+    let matches: Vec<(usize, &Range<usize>)> = matches
+        .iter()
+        .enumerate()
+        .filter(|(_, range)| {
+            let valid = range.start >= last_end
+                && range.end <= text.len()
+                && text.is_char_boundary(range.start)
+                && text.is_char_boundary(range.end);
+            if valid {
+                last_end = range.end;
+            }
+            valid
+        })
+        .collect();
+
+    let mut job = LayoutJob {
+        text: text.to_string(),
+        ..Default::default()
+    };
+    job.wrap.max_width = key.wrap_width;
+
+    let (mut span_index, mut match_index) = (0, 0);
+    let mut position = 0;
+
+    while position < text.len() {
+        while spans.get(span_index).is_some_and(|span| span.range.end <= position) {
+            span_index += 1;
+        }
+        while matches.get(match_index).is_some_and(|(_, range)| range.end <= position) {
+            match_index += 1;
+        }
+
+        let (kind, mut end) = match spans.get(span_index) {
+            Some(span) if span.range.start <= position => (Some(span.kind), span.range.end),
+            Some(span) => (None, span.range.start),
+            None => (None, text.len()),
+        };
+
+        let background = match matches.get(match_index) {
+            Some((index, range)) if range.start <= position => {
+                end = end.min(range.end);
+                Some(if key.current_match == Some(*index) {
+                    current_background
+                } else {
+                    match_background
+                })
+            }
+            Some((_, range)) => {
+                end = end.min(range.start);
+                None
+            }
+            None => None,
+        };
+
+        let mut format = match kind {
+            Some(kind) => token_format(kind, &plain, dark_mode),
+            None => plain.clone(),
+        };
+        if let Some(background) = background {
+            format.background = background;
+        }
+
+        job.sections.push(LayoutSection {
+            leading_space: 0.0,
+            byte_range: position..end,
+            format,
+        });
+        position = end;
+    }
+
+    if job.sections.is_empty() {
+        job.sections.push(LayoutSection {
+            leading_space: 0.0,
+            byte_range: 0..0,
+            format: plain,
+        });
+    }
+
+    job
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(cache: &mut HighlightCache, revision: u64, text: &str) -> Vec<(Range<usize>, TokenKind)> {
+        cache
+            .spans(LanguageMode::Rust, revision, text)
+            .iter()
+            .map(|span| (span.range.clone(), span.kind))
+            .collect()
+    }
+
+    #[test]
+    fn edits_match_lexing_from_scratch() {
+        let mut cache = HighlightCache::default();
+        let edits = [
+            "fn a() {}\nlet b = 1;\n// c\nfn d() {}",
+            "fn a() {}\n/* let b = 1;\n// c\nfn d() {}",
+            "fn a() {}\n/* let b = 1;\n// c */\nfn d() {}",
+            "fn a() {}\nlet b = 1;\n// c */\nfn d() {}",
+            "fn a() {}\nlet b = 1;\nfn d() {}",
+            "\"\nfn a() {}\nlet b = 1;\nfn d() {}",
+        ];
+
+        for (revision, text) in edits.iter().enumerate() {
+            let expected = tokens(&mut HighlightCache::default(), 0, text);
+            assert_eq!(tokens(&mut cache, revision as u64, text), expected, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn lexing_stops_once_the_state_matches_again() {
+        let mut cache = HighlightCache::default();
+        tokens(&mut cache, 0, "/* a\nb */\nfn c() {}\nfn d() {}");
+        cache.lines[3].spans.clear();
+
+        // Closing the comment early changes how the second line starts, but not the third.
+        tokens(&mut cache, 1, "/* a */\nb */\nfn c() {}\nfn d() {}");
+        assert_eq!(cache.lines[1].start_state, LineState::Normal);
+        assert!(cache.lines[3].spans.is_empty());
+    }
+}
@@ -0,0 +1,426 @@
+use crate::screens::notepad::line_ending::LineEnding;
+use std::collections::VecDeque;
+
+#[derive(Clone, Copy, PartialEq)]
+enum EditKind {
+    InsertWord,
+    InsertSpace,
+    Delete,
+    Other,
+}
+
+/// `removed` replaced by `inserted` at byte `offset` of the text.
+#[derive(Debug, PartialEq)]
+struct Edit {
+    offset: usize,
+    removed: String,
+    inserted: String,
+}
+
+impl Edit {
+    fn kind(&self) -> EditKind {
+        let mut inserted = self.inserted.chars();
+        match (self.removed.chars().count(), inserted.next(), inserted.next()) {
+            (0, Some('\n'), None) => EditKind::Other,
+            (0, Some(c), None) if c.is_whitespace() => EditKind::InsertSpace,
+            (0, Some(_), None) => EditKind::InsertWord,
+            (1, None, _) => EditKind::Delete,
+            _ => EditKind::Other,
+        }
+    }
+
+    fn apply(&self, text: &mut String) {
+        text.replace_range(self.offset..self.offset + self.removed.len(), &self.inserted);
+    }
+
+    fn revert(&self, text: &mut String) {
+        text.replace_range(self.offset..self.offset + self.inserted.len(), &self.removed);
+    }
+}
+
+/// The style a document is saved with and whether its file mixed several, which converting
+/// line endings changes without touching the text.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LineEndings {
+    pub line_ending: LineEnding,
+    pub mixed: bool,
+    pub unified: bool,
+}
+
+enum Step {
+    Text(Vec<Edit>),
+    LineEndings { before: LineEndings, after: LineEndings },
+}
+
+/// What an undo or redo changed in the document.
+#[derive(Debug, PartialEq)]
+pub enum Change {
+    /// The text was edited in place; the cursor should go to this char index.
+    Text { cursor: usize },
+    LineEndings(LineEndings),
+}
+
+/// Document-level undo/redo stack. Each step holds the edits made to the text rather than
+/// a copy of it. Typing is coalesced into word-sized groups as long as each edit continues
+/// where the last one stopped; everything else (pastes, replacements, transforms) is
+/// recorded as one step.
+#[derive(Default)]
+pub struct History {
+    undo_stack: VecDeque<Step>,
+    redo_stack: VecDeque<Step>,
+    /// Edits made in the editor since the last `observe`.
+    pending: Vec<Edit>,
+    last_kind: Option<EditKind>,
+    /// Byte offset where the last observed edit left the cursor.
+    last_end: usize,
+}
+
+impl History {
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Notes that the editor inserted `text` at byte `offset`.
+    pub fn inserted(&mut self, offset: usize, text: &str) {
+        // Keystrokes and a replaced selection arrive as several calls in the same frame.
+        if let Some(last) = self.pending.last_mut()
+            && offset == last.offset + last.inserted.len()
+        {
+            last.inserted.push_str(text);
+            return;
+        }
+
+        self.pending.push(Edit { offset, removed: String::new(), inserted: text.to_string() });
+    }
+
+    /// Notes that the editor removed `text` from byte `offset`.
+    pub fn removed(&mut self, offset: usize, text: &str) {
+        self.pending.push(Edit { offset, removed: text.to_string(), inserted: String::new() });
+    }
+
+    /// Records the edits made by the user in the text editor since the last call.
+    pub fn observe(&mut self, limit: usize) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        let edits = std::mem::take(&mut self.pending);
+        let (kind, start, removed_end, end) = match &edits[..] {
+            [edit] => (
+                edit.kind(),
+                edit.offset,
+                edit.offset + edit.removed.len(),
+                edit.offset + edit.inserted.len(),
+            ),
+            [.., last] => (EditKind::Other, last.offset, last.offset, last.offset + last.inserted.len()),
+            [] => unreachable!(),
+        };
+
+        // Backspace removes text ending at the cursor, typing and Delete start at it.
+        let adjacent = start == self.last_end || removed_end == self.last_end;
+        let continues_group = adjacent
+            && matches!(
+                (self.last_kind, kind),
+                (Some(EditKind::InsertWord), EditKind::InsertWord)
+                    | (Some(EditKind::InsertWord), EditKind::InsertSpace)
+                    | (Some(EditKind::InsertSpace), EditKind::InsertSpace)
+                    | (Some(EditKind::Delete), EditKind::Delete)
+            );
+
+        match self.undo_stack.back_mut() {
+            Some(Step::Text(step)) if continues_group => step.extend(edits),
+            _ => push_bounded(&mut self.undo_stack, Step::Text(edits), limit),
+        }
+
+        self.redo_stack.clear();
+        self.last_kind = Some(kind);
+        self.last_end = end;
+    }
+
+    /// Records a programmatic change from `before` to `after` as a single undoable step.
+    pub fn record(&mut self, before: &str, after: &str, limit: usize) {
+        if before == after {
+            return;
+        }
+
+        let (prefix, suffix) = common_affixes(before, after);
+        let edit = Edit {
+            offset: prefix,
+            removed: before[prefix..before.len() - suffix].to_string(),
+            inserted: after[prefix..after.len() - suffix].to_string(),
+        };
+
+        push_bounded(&mut self.undo_stack, Step::Text(vec![edit]), limit);
+        self.redo_stack.clear();
+        self.last_kind = None;
+    }
+
+    /// Records a line ending conversion as a single undoable step.
+    pub fn record_line_endings(&mut self, before: LineEndings, after: LineEndings, limit: usize) {
+        if before == after {
+            return;
+        }
+
+        push_bounded(&mut self.undo_stack, Step::LineEndings { before, after }, limit);
+        self.redo_stack.clear();
+        self.last_kind = None;
+    }
+
+    /// Reverts the last step. Text edits are undone in `text` directly.
+    pub fn undo(&mut self, text: &mut String, limit: usize) -> Option<Change> {
+        let step = self.undo_stack.pop_back()?;
+        let change = match &step {
+            Step::Text(edits) => {
+                for edit in edits.iter().rev() {
+                    edit.revert(text);
+                }
+                let first = &edits[0];
+                Change::Text { cursor: text[..first.offset + first.removed.len()].chars().count() }
+            }
+            Step::LineEndings { before, .. } => Change::LineEndings(*before),
+        };
+
+        push_bounded(&mut self.redo_stack, step, limit);
+        self.last_kind = None;
+
+        Some(change)
+    }
+
+    /// Reapplies the last undone step. Text edits are redone in `text` directly.
+    pub fn redo(&mut self, text: &mut String, limit: usize) -> Option<Change> {
+        let step = self.redo_stack.pop_back()?;
+        let change = match &step {
+            Step::Text(edits) => {
+                for edit in edits {
+                    edit.apply(text);
+                }
+                let last = &edits[edits.len() - 1];
+                Change::Text { cursor: text[..last.offset + last.inserted.len()].chars().count() }
+            }
+            Step::LineEndings { after, .. } => Change::LineEndings(*after),
+        };
+
+        push_bounded(&mut self.undo_stack, step, limit);
+        self.last_kind = None;
+
+        Some(change)
+    }
+}
+
+fn push_bounded(stack: &mut VecDeque<Step>, step: Step, limit: usize) {
+    stack.push_back(step);
+
+    while stack.len() > limit.max(1) {
+        stack.pop_front();
+    }
+}
+
+/// Byte lengths of the common prefix and suffix of two strings, on char boundaries.
+fn common_affixes(before: &str, after: &str) -> (usize, usize) {
+    let prefix: usize = before
+        .chars()
+        .zip(after.chars())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum();
+
+    let suffix: usize = before[prefix..]
+        .chars()
+        .rev()
+        .zip(after[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum();
+
+    (prefix, suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMIT: usize = 100;
+
+    /// Types `text` one char at a time at the end of `content`.
+    fn type_text(history: &mut History, content: &mut String, text: &str) {
+        for c in text.chars() {
+            history.inserted(content.len(), c.encode_utf8(&mut [0; 4]));
+            content.push(c);
+            history.observe(LIMIT);
+        }
+    }
+
+    /// Removes the char at byte `offset` of `content`, as Backspace or Delete would.
+    fn remove_char(history: &mut History, content: &mut String, offset: usize) {
+        let c = content.remove(offset);
+        history.removed(offset, c.encode_utf8(&mut [0; 4]));
+        history.observe(LIMIT);
+    }
+
+    fn cursor(cursor: usize) -> Option<Change> {
+        Some(Change::Text { cursor })
+    }
+
+    fn undo_all(history: &mut History, content: &mut String) -> Vec<String> {
+        std::iter::from_fn(|| history.undo(content, LIMIT).map(|_| content.clone())).collect()
+    }
+
+    #[test]
+    fn typing_is_grouped_by_word() {
+        let mut history = History::default();
+        let mut content = String::new();
+        type_text(&mut history, &mut content, "one two");
+
+        assert_eq!(undo_all(&mut history, &mut content), ["one ", ""]);
+    }
+
+    #[test]
+    fn a_line_break_is_its_own_step() {
+        let mut history = History::default();
+        let mut content = String::new();
+        type_text(&mut history, &mut content, "ab\ncd");
+
+        assert_eq!(undo_all(&mut history, &mut content), ["ab\n", "ab", ""]);
+    }
+
+    #[test]
+    fn backspaces_are_grouped() {
+        let mut history = History::default();
+        let mut content = "hello".to_string();
+        for offset in (2..5).rev() {
+            remove_char(&mut history, &mut content, offset);
+        }
+
+        assert_eq!(undo_all(&mut history, &mut content), ["hello"]);
+    }
+
+    #[test]
+    fn typing_somewhere_else_starts_a_new_group() {
+        let mut history = History::default();
+        let mut content = String::new();
+        type_text(&mut history, &mut content, "abc");
+
+        content.insert(0, 'x');
+        history.inserted(0, "x");
+        history.observe(LIMIT);
+        content.insert(1, 'y');
+        history.inserted(1, "y");
+        history.observe(LIMIT);
+
+        assert_eq!(undo_all(&mut history, &mut content), ["abc", ""]);
+    }
+
+    #[test]
+    fn deleting_somewhere_else_starts_a_new_group() {
+        let mut history = History::default();
+        let mut content = "abcdef".to_string();
+        remove_char(&mut history, &mut content, 5);
+        remove_char(&mut history, &mut content, 0);
+
+        assert_eq!(undo_all(&mut history, &mut content), ["abcde", "abcdef"]);
+    }
+
+    #[test]
+    fn forward_deletes_are_grouped() {
+        let mut history = History::default();
+        let mut content = "abcdef".to_string();
+        for _ in 0..3 {
+            remove_char(&mut history, &mut content, 2);
+        }
+
+        assert_eq!(undo_all(&mut history, &mut content), ["abcdef"]);
+    }
+
+    #[test]
+    fn replacing_a_selection_is_one_step() {
+        let mut history = History::default();
+        let mut content = "one two".to_string();
+        content.replace_range(4..7, "x");
+        history.removed(4, "two");
+        history.inserted(4, "x");
+        history.observe(LIMIT);
+
+        assert_eq!(history.undo(&mut content, LIMIT), cursor(7));
+        assert_eq!(content, "one two");
+    }
+
+    #[test]
+    fn recorded_changes_are_single_steps() {
+        let mut history = History::default();
+        let mut content = "a".to_string();
+        for next in ["b", "c", "c"] {
+            history.record(&content, next, LIMIT);
+            content = next.to_string();
+        }
+
+        assert_eq!(undo_all(&mut history, &mut content), ["b", "a"]);
+    }
+
+    #[test]
+    fn the_oldest_steps_are_dropped_past_the_limit() {
+        let mut history = History::default();
+        let mut content = "0".to_string();
+        for step in 1..=5 {
+            history.record(&content, &step.to_string(), 3);
+            content = step.to_string();
+        }
+
+        assert_eq!(undo_all(&mut history, &mut content), ["4", "3", "2"]);
+    }
+
+    #[test]
+    fn the_redo_stack_is_bounded_by_the_limit() {
+        let mut history = History::default();
+        let mut content = "0".to_string();
+        for step in 1..=5 {
+            history.record(&content, &step.to_string(), LIMIT);
+            content = step.to_string();
+        }
+
+        while history.undo(&mut content, 2).is_some() {}
+        assert_eq!(content, "0");
+        assert!(history.redo(&mut content, 2).is_some());
+        assert!(history.redo(&mut content, 2).is_some());
+        assert!(!history.can_redo());
+        assert_eq!(content, "2");
+    }
+
+    #[test]
+    fn redo_replays_undone_steps_until_a_new_edit() {
+        let mut history = History::default();
+        let mut content = "a".to_string();
+        history.record(&content, "ab", LIMIT);
+        content = "ab".to_string();
+
+        assert_eq!(history.undo(&mut content, LIMIT), cursor(1));
+        assert_eq!(content, "a");
+        assert_eq!(history.redo(&mut content, LIMIT), cursor(2));
+        assert_eq!(content, "ab");
+
+        history.undo(&mut content, LIMIT);
+        history.record(&content, "ax", LIMIT);
+        content = "ax".to_string();
+        assert!(!history.can_redo());
+        assert_eq!(history.undo(&mut content, LIMIT), cursor(1));
+        assert_eq!(content, "a");
+    }
+
+    #[test]
+    fn line_ending_conversions_are_single_steps() {
+        let mut history = History::default();
+        let mut content = "a\nb".to_string();
+        let mixed = LineEndings { line_ending: LineEnding::Lf, mixed: true, unified: false };
+        let converted = LineEndings { line_ending: LineEnding::CrLf, mixed: false, unified: true };
+        history.record_line_endings(mixed, converted, LIMIT);
+        history.record_line_endings(converted, converted, LIMIT);
+
+        assert_eq!(history.undo(&mut content, LIMIT), Some(Change::LineEndings(mixed)));
+        assert!(!history.can_undo());
+        assert_eq!(history.redo(&mut content, LIMIT), Some(Change::LineEndings(converted)));
+        assert_eq!(content, "a\nb");
+    }
+}
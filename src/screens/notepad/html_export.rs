@@ -0,0 +1,134 @@
+use crate::screens::notepad::highlighter::token_color;
+use crate::screens::notepad::syntax::{Span, TokenKind};
+use base64::Engine;
+use eframe::egui;
+use std::fmt::Write;
+use std::ops::Range;
+
+/// Family name the exported font is registered under in the page's `@font-face`.
+const EXPORT_FONT_FAMILY: &str = "NotepadR Export";
+
+pub struct HtmlOptions<'a> {
+    pub title: String,
+    /// Name of the editor font, used by the clipboard fragment where no font can be embedded.
+    pub font_name: String,
+    /// TrueType font embedded in standalone files.
+    pub font: &'a [u8],
+    pub font_size: f32,
+    pub dark_mode: bool,
+}
+
+fn css_color(color: egui::Color32) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b())
+}
+
+fn escape(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+}
+
+fn token_style(kind: TokenKind, dark_mode: bool) -> String {
+    let mut style = format!("color:{}", css_color(token_color(kind, dark_mode)));
+    if matches!(kind, TokenKind::Comment | TokenKind::Emphasis) {
+        style.push_str(";font-style:italic");
+    }
+    if kind == TokenKind::Link {
+        style.push_str(";text-decoration:underline");
+    }
+    style
+}
+
+/// Spans of the whole text clipped to `range` and made relative to its start.
+pub fn spans_in(spans: &[Span], range: Range<usize>) -> Vec<Span> {
+    spans
+        .iter()
+        .filter(|span| span.range.end > range.start && span.range.start < range.end)
+        .map(|span| Span {
+            range: span.range.start.max(range.start) - range.start
+                ..span.range.end.min(range.end) - range.start,
+            kind: span.kind,
+        })
+        .collect()
+}
+
+/// The text as escaped HTML with a coloured `<span>` per token.
+fn highlighted_body(text: &str, spans: &[Span], dark_mode: bool) -> String {
+    let mut body = String::with_capacity(text.len() * 2);
+    let mut position = 0;
+
+    for span in spans {
+        if span.range.start < position || span.range.end > text.len() {
+            continue;
+        }
+        escape(&text[position..span.range.start], &mut body);
+        let _ = write!(body, "<span style=\"{}\">", token_style(span.kind, dark_mode));
+        escape(&text[span.range.clone()], &mut body);
+        body.push_str("</span>");
+        position = span.range.end;
+    }
+    escape(&text[position..], &mut body);
+
+    body
+}
+
+fn pre_style(font_family: &str, options: &HtmlOptions) -> String {
+    let visuals = if options.dark_mode {
+        egui::Visuals::dark()
+    } else {
+        egui::Visuals::light()
+    };
+
+    format!(
+        "font-family:{},monospace;font-size:{}px;color:{};background:{};\
+         white-space:pre;tab-size:4;margin:0;padding:12px",
+        font_family,
+        options.font_size,
+        css_color(visuals.widgets.inactive.text_color()),
+        css_color(visuals.extreme_bg_color),
+    )
+}
+
+/// A standalone page with the font embedded, so it looks the same without the app installed.
+pub fn render_html(text: &str, spans: &[Span], options: &HtmlOptions) -> String {
+    let mut title = String::new();
+    escape(&options.title, &mut title);
+    let font = base64::engine::general_purpose::STANDARD.encode(options.font);
+    let style = pre_style(&format!("'{}'", EXPORT_FONT_FAMILY), options);
+
+    format!(
+        "<!DOCTYPE html>\n\
+         <html>\n\
+         <head>\n\
+         <meta charset=\"utf-8\">\n\
+         <title>{title}</title>\n\
+         <style>\n\
+         @font-face {{ font-family: '{EXPORT_FONT_FAMILY}'; src: url(data:font/ttf;base64,{font}) format('truetype'); }}\n\
+         body {{ margin: 0; }}\n\
+         pre {{ min-height: 100vh; box-sizing: border-box; }}\n\
+         </style>\n\
+         </head>\n\
+         <body>\n\
+         <pre style=\"{style}\">{body}</pre>\n\
+         </body>\n\
+         </html>\n",
+        body = highlighted_body(text, spans, options.dark_mode),
+    )
+}
+
+/// A single `<pre>` with inline styles, for pasting into documents and mail.
+pub fn render_html_fragment(text: &str, spans: &[Span], options: &HtmlOptions) -> String {
+    let style = pre_style(&format!("'{}'", options.font_name), options);
+    format!(
+        "<pre style=\"{}\">{}</pre>",
+        style,
+        highlighted_body(text, spans, options.dark_mode)
+    )
+}
@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+
+/// The editor buffer always holds `\n`; this is the style written to disk.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum LineEnding {
+    Lf,
+    CrLf,
+    Cr,
+}
+
+impl Default for LineEnding {
+    fn default() -> Self {
+        if cfg!(target_os = "windows") {
+            LineEnding::CrLf
+        } else {
+            LineEnding::Lf
+        }
+    }
+}
+
+impl LineEnding {
+    pub const ALL: [LineEnding; 3] = [LineEnding::Lf, LineEnding::CrLf, LineEnding::Cr];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+            LineEnding::Cr => "\r",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "LF",
+            LineEnding::CrLf => "CRLF",
+            LineEnding::Cr => "CR",
+        }
+    }
+}
+
+/// Returns the most common line ending in `text` and whether more than one style is used.
+pub fn detect(text: &str) -> (Option<LineEnding>, bool) {
+    let (mut lf, mut crlf, mut cr) = (0usize, 0usize, 0usize);
+    let mut bytes = text.bytes().peekable();
+
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'\r' if bytes.peek() == Some(&b'\n') => {
+                bytes.next();
+                crlf += 1;
+            }
+            b'\r' => cr += 1,
+            b'\n' => lf += 1,
+            _ => {}
+        }
+    }
+
+    let styles_used = [lf, crlf, cr].iter().filter(|count| **count > 0).count();
+    let dominant = if styles_used == 0 {
+        None
+    } else if crlf >= lf && crlf >= cr {
+        Some(LineEnding::CrLf)
+    } else if lf >= cr {
+        Some(LineEnding::Lf)
+    } else {
+        Some(LineEnding::Cr)
+    };
+
+    (dominant, styles_used > 1)
+}
+
+/// Converts every line ending to `\n`.
+pub fn normalize(text: &str) -> String {
+    if !text.contains('\r') {
+        return text.to_string();
+    }
+
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// Converts the `\n` line endings of a normalized buffer to `line_ending`.
+pub fn apply(text: &str, line_ending: LineEnding) -> String {
+    match line_ending {
+        LineEnding::Lf => text.to_string(),
+        _ => text.replace('\n', line_ending.as_str()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_a_single_style() {
+        assert_eq!(detect("a\nb\n"), (Some(LineEnding::Lf), false));
+        assert_eq!(detect("a\r\nb\r\n"), (Some(LineEnding::CrLf), false));
+        assert_eq!(detect("a\rb\r"), (Some(LineEnding::Cr), false));
+        assert_eq!(detect("no breaks"), (None, false));
+    }
+
+    #[test]
+    fn mixed_styles_report_the_most_common_one() {
+        assert_eq!(detect("a\r\nb\r\nc\nd"), (Some(LineEnding::CrLf), true));
+        assert_eq!(detect("a\nb\nc\r\nd"), (Some(LineEnding::Lf), true));
+        assert_eq!(detect("a\rb\rc\nd"), (Some(LineEnding::Cr), true));
+    }
+
+    #[test]
+    fn normalize_turns_every_style_into_lf() {
+        assert_eq!(normalize("a\r\nb\rc\nd"), "a\nb\nc\nd");
+        assert_eq!(normalize("\r\r\n"), "\n\n");
+    }
+
+    #[test]
+    fn apply_round_trips_through_normalize() {
+        let text = "one\ntwo\n\nthree";
+        for line_ending in LineEnding::ALL {
+            let applied = apply(text, line_ending);
+            assert_eq!(detect(&applied), (Some(line_ending), false));
+            assert_eq!(normalize(&applied), text);
+        }
+    }
+}
@@ -0,0 +1,575 @@
+use crate::app_state::AppState;
+use crate::screens::notepad::syntax::LanguageMode;
+use eframe::egui;
+use eframe::egui::text_edit::TextEditOutput;
+use pulldown_cmark::{Alignment, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::Path;
+
+const LIST_INDENT: f32 = 20.0;
+const QUOTE_INDENT: f32 = 12.0;
+const BLOCK_SPACING: f32 = 8.0;
+
+#[derive(Clone, Copy, Default)]
+struct InlineStyle {
+    strong: bool,
+    emphasis: bool,
+    strikethrough: bool,
+    code: bool,
+}
+
+enum Inline {
+    Text {
+        text: String,
+        style: InlineStyle,
+        link: Option<String>,
+    },
+    Image {
+        url: String,
+        alt: String,
+    },
+    LineBreak,
+}
+
+enum Marker {
+    Bullet,
+    Number(u64),
+    Task(bool),
+}
+
+enum BlockKind {
+    Heading(HeadingLevel, Vec<Inline>),
+    Paragraph(Vec<Inline>),
+    Code(String),
+    Rule,
+    Table {
+        alignments: Vec<Alignment>,
+        header: Vec<Vec<Inline>>,
+        rows: Vec<Vec<Vec<Inline>>>,
+    },
+}
+
+/// A top-level piece of the preview. Lists and quotes are flattened into their blocks, which
+/// remember how deeply they are nested instead.
+struct Block {
+    kind: BlockKind,
+    /// 0-based line of the source the block starts on, used to keep both sides in step.
+    line: usize,
+    list_depth: usize,
+    quote_depth: usize,
+    /// Bullet, number or checkbox of the list item this block opens.
+    marker: Option<Marker>,
+}
+
+struct TableState {
+    alignments: Vec<Alignment>,
+    header: Vec<Vec<Inline>>,
+    rows: Vec<Vec<Vec<Inline>>>,
+    row: Vec<Vec<Inline>>,
+}
+
+/// Turns the parser events into blocks.
+#[derive(Default)]
+struct Builder {
+    blocks: Vec<Block>,
+    inlines: Vec<Inline>,
+    /// Byte offset where the pending inlines started.
+    start: Option<usize>,
+    style: InlineStyle,
+    links: Vec<String>,
+    /// Alt text being collected for an image.
+    image: Option<(String, String)>,
+    code: Option<String>,
+    /// Next number of each open list, `None` for bullet lists.
+    lists: Vec<Option<u64>>,
+    quote_depth: usize,
+    marker: Option<Marker>,
+    table: Option<TableState>,
+    line_starts: Vec<usize>,
+}
+
+impl Builder {
+    fn line_of(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|start| *start <= offset).saturating_sub(1)
+    }
+
+    fn push(&mut self, kind: BlockKind, offset: usize) {
+        let start = self.start.take().unwrap_or(offset);
+        let line = self.line_of(start);
+        self.blocks.push(Block {
+            kind,
+            line,
+            list_depth: self.lists.len(),
+            quote_depth: self.quote_depth,
+            marker: self.marker.take(),
+        });
+    }
+
+    /// Ends the paragraph in progress, which tight list items leave untagged.
+    fn flush(&mut self, offset: usize) {
+        if self.inlines.is_empty() {
+            self.start = None;
+        } else {
+            let inlines = std::mem::take(&mut self.inlines);
+            self.push(BlockKind::Paragraph(inlines), offset);
+        }
+    }
+
+    fn text(&mut self, text: &str, offset: usize, code: bool) {
+        if let Some(block) = &mut self.code {
+            block.push_str(text);
+        } else if let Some((_, alt)) = &mut self.image {
+            alt.push_str(text);
+        } else {
+            self.start.get_or_insert(offset);
+            self.inlines.push(Inline::Text {
+                text: text.to_string(),
+                style: InlineStyle { code, ..self.style },
+                link: self.links.last().cloned(),
+            });
+        }
+    }
+
+    fn start_tag(&mut self, tag: Tag, offset: usize) {
+        match tag {
+            Tag::Paragraph | Tag::Heading { .. } | Tag::HtmlBlock => {
+                self.flush(offset);
+                self.start = Some(offset);
+            }
+            Tag::CodeBlock(_) => {
+                self.flush(offset);
+                self.start = Some(offset);
+                self.code = Some(String::new());
+            }
+            Tag::List(first) => {
+                self.flush(offset);
+                self.lists.push(first);
+            }
+            Tag::Item => {
+                self.flush(offset);
+                self.marker = Some(match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        Marker::Number(*number - 1)
+                    }
+                    _ => Marker::Bullet,
+                });
+            }
+            Tag::BlockQuote(_) => {
+                self.flush(offset);
+                self.quote_depth += 1;
+            }
+            Tag::Table(alignments) => {
+                self.flush(offset);
+                self.start = Some(offset);
+                self.table = Some(TableState {
+                    alignments,
+                    header: Vec::new(),
+                    rows: Vec::new(),
+                    row: Vec::new(),
+                });
+            }
+            Tag::TableCell => self.inlines.clear(),
+            Tag::Emphasis => self.style.emphasis = true,
+            Tag::Strong => self.style.strong = true,
+            Tag::Strikethrough => self.style.strikethrough = true,
+            Tag::Link { dest_url, .. } => self.links.push(dest_url.to_string()),
+            Tag::Image { dest_url, .. } => self.image = Some((dest_url.to_string(), String::new())),
+            _ => {}
+        }
+    }
+
+    fn end_tag(&mut self, tag: TagEnd, offset: usize) {
+        match tag {
+            TagEnd::Paragraph | TagEnd::HtmlBlock => self.flush(offset),
+            TagEnd::Heading(level) => {
+                let inlines = std::mem::take(&mut self.inlines);
+                self.push(BlockKind::Heading(level, inlines), offset);
+            }
+            TagEnd::CodeBlock => {
+                let code = self.code.take().unwrap_or_default();
+                self.push(BlockKind::Code(code.trim_end_matches('\n').to_string()), offset);
+            }
+            TagEnd::List(_) => {
+                self.flush(offset);
+                self.lists.pop();
+            }
+            TagEnd::Item => self.flush(offset),
+            TagEnd::BlockQuote(_) => {
+                self.flush(offset);
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+            }
+            TagEnd::TableCell => {
+                let cell = std::mem::take(&mut self.inlines);
+                if let Some(table) = &mut self.table {
+                    table.row.push(cell);
+                }
+            }
+            TagEnd::TableHead => {
+                if let Some(table) = &mut self.table {
+                    table.header = std::mem::take(&mut table.row);
+                }
+            }
+            TagEnd::TableRow => {
+                if let Some(table) = &mut self.table {
+                    let row = std::mem::take(&mut table.row);
+                    table.rows.push(row);
+                }
+            }
+            TagEnd::Table => {
+                if let Some(table) = self.table.take() {
+                    let kind = BlockKind::Table {
+                        alignments: table.alignments,
+                        header: table.header,
+                        rows: table.rows,
+                    };
+                    self.push(kind, offset);
+                }
+            }
+            TagEnd::Emphasis => self.style.emphasis = false,
+            TagEnd::Strong => self.style.strong = false,
+            TagEnd::Strikethrough => self.style.strikethrough = false,
+            TagEnd::Link => {
+                self.links.pop();
+            }
+            TagEnd::Image => {
+                if let Some((url, alt)) = self.image.take() {
+                    self.start.get_or_insert(offset);
+                    self.inlines.push(Inline::Image { url, alt });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn parse(text: &str) -> Vec<Block> {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_GFM;
+    let mut builder = Builder {
+        line_starts: std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(index, _)| index + 1))
+            .collect(),
+        ..Default::default()
+    };
+
+    for (event, range) in Parser::new_ext(text, options).into_offset_iter() {
+        let offset = range.start;
+        match event {
+            Event::Start(tag) => builder.start_tag(tag, offset),
+            Event::End(tag) => builder.end_tag(tag, offset),
+            Event::Text(text) => builder.text(&text, offset, false),
+            Event::Code(text) => builder.text(&text, offset, true),
+            Event::Html(html) | Event::InlineHtml(html) => builder.text(&html, offset, true),
+            Event::SoftBreak => builder.text(" ", offset, false),
+            Event::HardBreak => builder.inlines.push(Inline::LineBreak),
+            Event::Rule => {
+                builder.flush(offset);
+                builder.push(BlockKind::Rule, offset);
+            }
+            Event::TaskListMarker(checked) => builder.marker = Some(Marker::Task(checked)),
+            _ => {}
+        }
+    }
+
+    let end = text.len();
+    builder.flush(end);
+    builder.blocks
+}
+
+/// Rendered Markdown of a document, re-parsed only when its text changes, and where each
+/// block was last drawn.
+#[derive(Default)]
+pub struct MarkdownPreview {
+    text_hash: Option<u64>,
+    blocks: Vec<Block>,
+    /// Source line and preview offset of each block, in order.
+    anchors: Vec<(f32, f32)>,
+    /// First editor line on screen, with the fraction of it scrolled past.
+    pub editor_line: f32,
+    /// Editor line the preview was last scrolled to.
+    synced_line: Option<f32>,
+}
+
+impl MarkdownPreview {
+    fn update(&mut self, text: &str) {
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+        let hash = hasher.finish();
+        if self.text_hash != Some(hash) {
+            self.blocks = parse(text);
+            self.text_hash = Some(hash);
+        }
+    }
+
+    /// Preview offset matching a source line, between the blocks around it.
+    fn offset_of_line(&self, line: f32) -> Option<f32> {
+        let next = self.anchors.partition_point(|(start, _)| *start <= line);
+        let (line_before, offset_before) = *self.anchors.get(next.checked_sub(1)?)?;
+        match self.anchors.get(next) {
+            Some((line_after, offset_after)) => {
+                let fraction = (line - line_before) / (line_after - line_before);
+                Some(offset_before + fraction * (offset_after - offset_before))
+            }
+            None => Some(offset_before),
+        }
+    }
+}
+
+/// First visible line of the editor (0-based), counting wrapped rows as fractions of it.
+pub fn top_visible_line(output: &TextEditOutput, top: f32) -> f32 {
+    let origin = output.galley_pos.to_vec2();
+    let mut line = 0;
+    let mut line_top = None;
+
+    for row in &output.galley.rows {
+        let rect = row.rect().translate(origin);
+        let start = *line_top.get_or_insert(rect.top());
+        if row.ends_with_newline || rect.bottom() > top {
+            if rect.bottom() > top {
+                let height = (rect.bottom() - start).max(1.0);
+                return line as f32 + ((top - start) / height).clamp(0.0, 1.0);
+            }
+            line += 1;
+            line_top = None;
+        }
+    }
+    line as f32
+}
+
+fn inline_text(ui: &egui::Ui, text: &str, style: InlineStyle, size: Option<f32>) -> egui::RichText {
+    let mut rich = egui::RichText::new(text);
+    if let Some(size) = size {
+        rich = rich.size(size).strong();
+    }
+    if style.strong {
+        rich = rich.strong();
+    }
+    if style.emphasis {
+        rich = rich.italics();
+    }
+    if style.strikethrough {
+        rich = rich.strikethrough();
+    }
+    if style.code {
+        rich = rich.code().color(ui.visuals().text_color());
+    }
+    rich
+}
+
+fn image_uri(url: &str, base_dir: Option<&Path>) -> Option<String> {
+    if url.contains("://") {
+        return url.starts_with("file://").then(|| url.to_string());
+    }
+    let path = Path::new(url);
+    let path = match base_dir {
+        Some(directory) if path.is_relative() => directory.join(path),
+        _ => path.to_path_buf(),
+    };
+    Some(format!("file://{}", path.display()))
+}
+
+fn show_inlines(ui: &mut egui::Ui, inlines: &[Inline], size: Option<f32>, base_dir: Option<&Path>) {
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 0.0;
+        for inline in inlines {
+            match inline {
+                Inline::Text { text, style, link: Some(url) } => {
+                    ui.hyperlink_to(inline_text(ui, text, *style, size), url);
+                }
+                Inline::Text { text, style, link: None } => {
+                    ui.label(inline_text(ui, text, *style, size));
+                }
+                Inline::Image { url, alt } => match image_uri(url, base_dir) {
+                    Some(uri) => {
+                        ui.add(
+                            egui::Image::new(uri)
+                                .alt_text(alt)
+                                .max_width(ui.available_width())
+                                .fit_to_original_size(1.0),
+                        );
+                    }
+                    None => {
+                        ui.hyperlink_to(format!("🖼 {}", alt), url);
+                    }
+                },
+                Inline::LineBreak => ui.end_row(),
+            }
+        }
+    });
+}
+
+fn show_table(
+    ui: &mut egui::Ui,
+    id: usize,
+    alignments: &[Alignment],
+    header: &[Vec<Inline>],
+    rows: &[Vec<Vec<Inline>>],
+    base_dir: Option<&Path>,
+) {
+    let cell = |ui: &mut egui::Ui, column: usize, inlines: &[Inline], header: bool| {
+        let align = match alignments.get(column) {
+            Some(Alignment::Center) => egui::Align::Center,
+            Some(Alignment::Right) => egui::Align::Max,
+            _ => egui::Align::Min,
+        };
+        ui.with_layout(egui::Layout::top_down(align), |ui| {
+            if header {
+                ui.style_mut().override_text_style = Some(egui::TextStyle::Button);
+                ui.visuals_mut().override_text_color = Some(ui.visuals().strong_text_color());
+            }
+            show_inlines(ui, inlines, None, base_dir);
+        });
+    };
+
+    egui::Frame::group(ui.style()).show(ui, |ui| {
+        egui::Grid::new(("markdown_table", id))
+            .striped(true)
+            .spacing([16.0, 4.0])
+            .show(ui, |ui| {
+                for (column, inlines) in header.iter().enumerate() {
+                    cell(ui, column, inlines, true);
+                }
+                ui.end_row();
+                for row in rows {
+                    for (column, inlines) in row.iter().enumerate() {
+                        cell(ui, column, inlines, false);
+                    }
+                    ui.end_row();
+                }
+            });
+    });
+}
+
+fn show_block(ui: &mut egui::Ui, index: usize, block: &Block, base_dir: Option<&Path>) {
+    let body_size = egui::TextStyle::Body.resolve(ui.style()).size;
+    let quote_width = block.quote_depth as f32 * QUOTE_INDENT;
+    let list_width = block.list_depth as f32 * LIST_INDENT;
+    let left = ui.cursor().left();
+
+    let response = ui.horizontal_top(|ui| {
+        ui.spacing_mut().item_spacing.x = 0.0;
+        ui.add_space(quote_width + (list_width - LIST_INDENT).max(0.0));
+        if block.list_depth > 0 {
+            let marker_size = egui::vec2(LIST_INDENT, body_size);
+            match block.marker {
+                Some(Marker::Bullet) => {
+                    let bullet = ["•", "◦", "▪"][(block.list_depth - 1) % 3];
+                    ui.add_sized(marker_size, egui::Label::new(bullet));
+                }
+                Some(Marker::Number(number)) => {
+                    ui.add_sized(marker_size, egui::Label::new(format!("{}.", number)));
+                }
+                Some(Marker::Task(checked)) => {
+                    let mut checked = checked;
+                    ui.add_enabled_ui(false, |ui| ui.checkbox(&mut checked, ""));
+                }
+                None => ui.add_space(LIST_INDENT),
+            }
+        }
+
+        ui.vertical(|ui| match &block.kind {
+            BlockKind::Heading(level, inlines) => {
+                let scale = match level {
+                    HeadingLevel::H1 => 1.8,
+                    HeadingLevel::H2 => 1.5,
+                    HeadingLevel::H3 => 1.3,
+                    HeadingLevel::H4 => 1.15,
+                    HeadingLevel::H5 | HeadingLevel::H6 => 1.0,
+                };
+                show_inlines(ui, inlines, Some(body_size * scale), base_dir);
+                if matches!(level, HeadingLevel::H1 | HeadingLevel::H2) {
+                    ui.separator();
+                }
+            }
+            BlockKind::Paragraph(inlines) => show_inlines(ui, inlines, None, base_dir),
+            BlockKind::Code(code) => {
+                egui::Frame::new()
+                    .fill(ui.visuals().code_bg_color)
+                    .inner_margin(8.0)
+                    .corner_radius(4.0)
+                    .show(ui, |ui| {
+                        ui.set_width(ui.available_width());
+                        ui.label(egui::RichText::new(code).monospace());
+                    });
+            }
+            BlockKind::Rule => {
+                ui.separator();
+            }
+            BlockKind::Table { alignments, header, rows } => {
+                show_table(ui, index, alignments, header, rows, base_dir);
+            }
+        });
+    });
+
+    let rect = response.response.rect;
+    let stroke = egui::Stroke::new(3.0, ui.visuals().widgets.noninteractive.bg_stroke.color);
+    for depth in 0..block.quote_depth {
+        let x = left + depth as f32 * QUOTE_INDENT + 2.0;
+        ui.painter().vline(x, rect.y_range(), stroke);
+    }
+}
+
+/// Side panel with the active document rendered, when it is Markdown and its preview is on.
+pub fn app_markdown_preview(state: &mut AppState, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    let default_path = state.settings_state.current.default_path.clone();
+    let document = state.notepad_state.active_mut();
+    if !document.show_preview || document.language_mode != LanguageMode::Markdown {
+        return;
+    }
+
+    // Relative image paths are resolved like links in the file itself.
+    let base_dir = match &document.current_file_path {
+        Some(path) => path.parent().map(Path::to_path_buf),
+        None if !default_path.is_empty() => Some(default_path.into()),
+        None => None,
+    };
+
+    egui::SidePanel::right("markdown_preview")
+        .resizable(true)
+        .default_width(ctx.screen_rect().width() / 2.0)
+        .show(ctx, |ui| {
+            show_markdown_preview(
+                ui,
+                document.id,
+                &mut document.preview,
+                &document.current_content,
+                base_dir.as_deref(),
+            );
+        });
+}
+
+/// Shows the rendered `text`, scrolled to follow the editor while the user scrolls it.
+fn show_markdown_preview(
+    ui: &mut egui::Ui,
+    id: u64,
+    preview: &mut MarkdownPreview,
+    text: &str,
+    base_dir: Option<&Path>,
+) {
+    preview.update(text);
+
+    let editor_line = preview.editor_line;
+    let mut scroll_area = egui::ScrollArea::vertical()
+        .id_salt(("markdown_preview_scroll", id))
+        .auto_shrink([false; 2]);
+    if preview.synced_line != Some(editor_line)
+        && let Some(offset) = preview.offset_of_line(editor_line)
+    {
+        scroll_area = scroll_area.vertical_scroll_offset(offset);
+        preview.synced_line = Some(editor_line);
+    }
+
+    let mut anchors = Vec::with_capacity(preview.blocks.len());
+    scroll_area.show(ui, |ui| {
+        let content_top = ui.min_rect().top();
+        for (index, block) in preview.blocks.iter().enumerate() {
+            anchors.push((block.line as f32, ui.cursor().top() - content_top));
+            show_block(ui, index, block, base_dir);
+            ui.add_space(BLOCK_SPACING);
+        }
+    });
+    preview.anchors = anchors;
+}
@@ -1,7 +1,18 @@
-use crate::app_state::{AppState, PendingAction};
+use crate::app_state::{
+    AppState, CommandPalette, EncodingDialogMode, GoToLineDialog, PendingAction, WordWrap,
+};
 use crate::navigator::Screen;
-use crate::screens::notepad::commands::{new_file, open_file, save};
+use crate::recent_files::{clear_recent_files, open_recent_file};
+use crate::screens::find_in_files::find_in_files_screen::prepare_find_in_files;
+use crate::screens::notepad::commands::{
+    close_document, convert_line_endings, copy_as_html, export_html, export_pdf, new_file,
+    open_encoding_dialog, open_file, open_find, print_document, redo, save, save_as, save_copy_as,
+    set_zoom, undo, zoom_in, zoom_out,
+};
+use crate::screens::notepad::line_ending::LineEnding;
+use crate::screens::notepad::syntax::LanguageMode;
 use eframe::egui;
+use std::path::PathBuf;
 
 pub fn app_menu_topbar(state: &mut AppState, ctx: &egui::Context, _frame: &mut eframe::Frame) {
     egui::TopBottomPanel::top("menu_topbar").show(ctx, |ui| {
@@ -15,9 +26,200 @@ pub fn app_menu_topbar(state: &mut AppState, ctx: &egui::Context, _frame: &mut e
                     on_open_button_clicked(state);
                 }
 
+                let has_recent = !state.recent_files.is_empty();
+                ui.add_enabled_ui(has_recent, |ui| {
+                    ui.menu_button(state.text("notepad.menu.file.open.recent"), |ui| {
+                        let limit = state.settings_state.current.recent_files_limit;
+                        for path in state.recent_files.iter().take(limit).cloned().collect::<Vec<_>>() {
+                            if ui.button(path.display().to_string()).clicked() {
+                                on_open_recent_clicked(state, path);
+                                ui.close();
+                            }
+                        }
+
+                        ui.separator();
+
+                        if ui.button(state.text("notepad.menu.file.open.recent.clear")).clicked() {
+                            clear_recent_files(state);
+                            ui.close();
+                        }
+                    });
+                });
+
                 if ui.button(state.text("notepad.menu.file.save")).clicked() {
                     on_save_button_clicked(state);
                 }
+
+                if ui.button(state.text("notepad.menu.file.save.as")).clicked() {
+                    on_save_as_button_clicked(state);
+                }
+
+                if ui.button(state.text("notepad.menu.file.save.copy")).clicked() {
+                    on_save_copy_button_clicked(state);
+                }
+
+                let document = state.notepad_state.active();
+                let can_revert = document.current_file_path.is_some() && document.is_dirty();
+                if ui
+                    .add_enabled(can_revert, egui::Button::new(state.text("notepad.menu.file.revert")))
+                    .clicked()
+                {
+                    on_revert_button_clicked(state);
+                }
+
+                ui.separator();
+
+                let has_path = state.notepad_state.active().current_file_path.is_some();
+                if ui
+                    .add_enabled(has_path, egui::Button::new(state.text("notepad.menu.file.reopen.encoding")))
+                    .clicked()
+                {
+                    on_reopen_with_encoding_clicked(state);
+                }
+
+                if ui.button(state.text("notepad.menu.file.save.encoding")).clicked() {
+                    on_save_with_encoding_clicked(state);
+                }
+
+                ui.separator();
+
+                if ui.button(state.text("notepad.menu.file.export.pdf")).clicked() {
+                    on_export_pdf_clicked(state);
+                }
+
+                if ui.button(state.text("notepad.menu.file.export.html")).clicked() {
+                    on_export_html_clicked(state);
+                }
+
+                if ui.button(state.text("notepad.menu.file.print")).clicked() {
+                    on_print_clicked(state);
+                }
+
+                ui.separator();
+
+                if ui.button(state.text("notepad.menu.file.close")).clicked() {
+                    on_close_tab_clicked(state, state.notepad_state.active_document);
+                }
+            });
+
+            ui.menu_button(state.text("notepad.menu.edit"), |ui| {
+                let can_undo = state.notepad_state.active().history.can_undo();
+                if ui.add_enabled(can_undo, egui::Button::new(state.text("notepad.menu.edit.undo"))).clicked() {
+                    on_undo_button_clicked(state);
+                }
+
+                let can_redo = state.notepad_state.active().history.can_redo();
+                if ui.add_enabled(can_redo, egui::Button::new(state.text("notepad.menu.edit.redo"))).clicked() {
+                    on_redo_button_clicked(state);
+                }
+
+                ui.separator();
+
+                if ui.button(state.text("notepad.menu.edit.copy.html")).clicked() {
+                    on_copy_as_html_clicked(state);
+                }
+
+                ui.separator();
+
+                if ui.button(state.text("notepad.menu.edit.find")).clicked() {
+                    on_find_button_clicked(state);
+                }
+
+                if ui.button(state.text("notepad.menu.edit.replace")).clicked() {
+                    on_replace_button_clicked(state);
+                }
+
+                if ui.button(state.text("notepad.menu.edit.find.in.files")).clicked() {
+                    on_find_in_files_button_clicked(state);
+                }
+
+                if ui.button(state.text("notepad.menu.edit.go.to.line")).clicked() {
+                    on_go_to_line_button_clicked(state);
+                }
+
+                ui.separator();
+
+                ui.menu_button(state.text("notepad.menu.edit.line.endings"), |ui| {
+                    let current = state.notepad_state.active().line_ending;
+                    for line_ending in LineEnding::ALL {
+                        if ui.radio(current == line_ending, line_ending.label()).clicked() {
+                            convert_line_endings(state, line_ending);
+                            ui.close();
+                        }
+                    }
+                });
+            });
+
+            ui.menu_button(state.text("notepad.menu.view"), |ui| {
+                if ui.button(state.text("notepad.menu.view.command.palette")).clicked() {
+                    on_command_palette_clicked(state);
+                }
+
+                ui.separator();
+
+                let mut show_line_numbers = state.settings_state.current.show_line_numbers;
+                if ui.checkbox(&mut show_line_numbers, state.text("notepad.menu.view.line.numbers")).clicked() {
+                    on_line_numbers_toggled(state, show_line_numbers);
+                }
+
+                ui.menu_button(state.text("notepad.menu.view.word.wrap"), |ui| {
+                    let word_wrap = state.settings_state.current.word_wrap;
+                    let mut wrap_column = state.settings_state.current.wrap_column;
+
+                    if ui.radio(word_wrap == WordWrap::Off, state.text("notepad.menu.view.word.wrap.off")).clicked() {
+                        on_word_wrap_selected(state, WordWrap::Off, wrap_column);
+                    }
+                    if ui
+                        .radio(word_wrap == WordWrap::Window, state.text("notepad.menu.view.word.wrap.window"))
+                        .clicked()
+                    {
+                        on_word_wrap_selected(state, WordWrap::Window, wrap_column);
+                    }
+
+                    ui.horizontal(|ui| {
+                        if ui
+                            .radio(word_wrap == WordWrap::Column, state.text("notepad.menu.view.word.wrap.column"))
+                            .clicked()
+                        {
+                            on_word_wrap_selected(state, WordWrap::Column, wrap_column);
+                        }
+
+                        let response = ui.add(egui::DragValue::new(&mut wrap_column).range(20..=400));
+                        if response.changed() {
+                            // Follow the value live, but only write the config once dragging ends.
+                            state.settings_state.current.wrap_column = wrap_column;
+                            state.settings_state.unsaved.wrap_column = wrap_column;
+                        }
+                        if response.drag_stopped() || (response.changed() && !response.dragged()) {
+                            on_word_wrap_selected(state, WordWrap::Column, wrap_column);
+                        }
+                    });
+                });
+
+                let document = state.notepad_state.active();
+                let is_markdown = document.language_mode == LanguageMode::Markdown;
+                let mut show_preview = document.show_preview;
+                let preview_text = state.text("notepad.menu.view.markdown.preview");
+                if ui
+                    .add_enabled(is_markdown, egui::Checkbox::new(&mut show_preview, preview_text))
+                    .clicked()
+                {
+                    on_markdown_preview_toggled(state, show_preview);
+                }
+
+                ui.separator();
+
+                if ui.button(state.text("notepad.menu.view.zoom.in")).clicked() {
+                    on_zoom_in_clicked(state);
+                }
+
+                if ui.button(state.text("notepad.menu.view.zoom.out")).clicked() {
+                    on_zoom_out_clicked(state);
+                }
+
+                if ui.button(state.text("notepad.menu.view.zoom.reset")).clicked() {
+                    on_zoom_reset_clicked(state);
+                }
             });
 
             if ui.button(state.text("notepad.menu.settings")).clicked() {
@@ -28,23 +230,143 @@ pub fn app_menu_topbar(state: &mut AppState, ctx: &egui::Context, _frame: &mut e
 }
 
 pub fn on_new_button_clicked(state: &mut AppState) {
-    if state.has_unsaved_changes() {
-        state.notepad_state.show_save_modal = true;
-        state.notepad_state.pending_action = PendingAction::NewFile;
-    } else {
-        new_file(state);
-    }
+    new_file(state);
 }
 
 pub fn on_open_button_clicked(state: &mut AppState) {
-    if state.has_unsaved_changes() {
-        state.notepad_state.show_save_modal = true;
-        state.notepad_state.pending_action = PendingAction::OpenFile;
-    } else {
-        open_file(state);
-    }
+    open_file(state);
+}
+
+pub fn on_open_recent_clicked(state: &mut AppState, path: PathBuf) {
+    open_recent_file(state, path);
 }
 
 pub fn on_save_button_clicked(state: &mut AppState) {
     save(state);
 }
+
+pub fn on_save_as_button_clicked(state: &mut AppState) {
+    save_as(state);
+}
+
+pub fn on_save_copy_button_clicked(state: &mut AppState) {
+    save_copy_as(state);
+}
+
+pub fn on_revert_button_clicked(state: &mut AppState) {
+    let document = state.notepad_state.active();
+    if document.current_file_path.is_some() && document.is_dirty() {
+        state.notepad_state.show_revert_modal = true;
+    }
+}
+
+pub fn on_reopen_with_encoding_clicked(state: &mut AppState) {
+    if state.notepad_state.active().current_file_path.is_some() {
+        open_encoding_dialog(state, EncodingDialogMode::Reopen);
+    }
+}
+
+pub fn on_save_with_encoding_clicked(state: &mut AppState) {
+    open_encoding_dialog(state, EncodingDialogMode::Save);
+}
+
+pub fn on_export_pdf_clicked(state: &mut AppState) {
+    export_pdf(state);
+}
+
+pub fn on_export_html_clicked(state: &mut AppState) {
+    export_html(state);
+}
+
+pub fn on_copy_as_html_clicked(state: &mut AppState) {
+    copy_as_html(state);
+}
+
+pub fn on_print_clicked(state: &mut AppState) {
+    print_document(state);
+}
+
+pub fn on_language_mode_selected(state: &mut AppState, language_mode: LanguageMode) {
+    state.notepad_state.active_mut().language_mode = language_mode;
+}
+
+pub fn on_markdown_preview_toggled(state: &mut AppState, show_preview: bool) {
+    state.notepad_state.active_mut().show_preview = show_preview;
+}
+
+pub fn on_line_numbers_toggled(state: &mut AppState, show_line_numbers: bool) {
+    state.settings_state.current.show_line_numbers = show_line_numbers;
+    state.settings_state.unsaved.show_line_numbers = show_line_numbers;
+
+    if let Err(e) = state.save_settings_to_disk() {
+        log::error!("ERROR: saving configuration -> {}", e);
+    }
+}
+
+pub fn on_word_wrap_selected(state: &mut AppState, word_wrap: WordWrap, wrap_column: usize) {
+    state.settings_state.current.word_wrap = word_wrap;
+    state.settings_state.current.wrap_column = wrap_column;
+    state.settings_state.unsaved.word_wrap = word_wrap;
+    state.settings_state.unsaved.wrap_column = wrap_column;
+
+    if let Err(e) = state.save_settings_to_disk() {
+        log::error!("ERROR: saving configuration -> {}", e);
+    }
+}
+
+pub fn on_zoom_in_clicked(state: &mut AppState) {
+    zoom_in(state);
+}
+
+pub fn on_zoom_out_clicked(state: &mut AppState) {
+    zoom_out(state);
+}
+
+pub fn on_zoom_reset_clicked(state: &mut AppState) {
+    set_zoom(state, 1.0);
+}
+
+pub fn on_undo_button_clicked(state: &mut AppState) {
+    undo(state);
+}
+
+pub fn on_redo_button_clicked(state: &mut AppState) {
+    redo(state);
+}
+
+pub fn on_find_button_clicked(state: &mut AppState) {
+    open_find(state, false);
+}
+
+pub fn on_replace_button_clicked(state: &mut AppState) {
+    open_find(state, true);
+}
+
+pub fn on_command_palette_clicked(state: &mut AppState) {
+    state.notepad_state.command_palette = Some(CommandPalette {
+        focus_query: true,
+        ..Default::default()
+    });
+}
+
+pub fn on_go_to_line_button_clicked(state: &mut AppState) {
+    state.notepad_state.go_to_line_dialog = Some(GoToLineDialog {
+        focus_input: true,
+        ..Default::default()
+    });
+}
+
+pub fn on_find_in_files_button_clicked(state: &mut AppState) {
+    prepare_find_in_files(state);
+    state.screen = Screen::FindInFiles;
+}
+
+pub fn on_close_tab_clicked(state: &mut AppState, index: usize) {
+    if state.notepad_state.documents[index].is_dirty() {
+        state.notepad_state.active_document = index;
+        state.notepad_state.show_save_modal = true;
+        state.notepad_state.pending_action = PendingAction::CloseTab(index);
+    } else {
+        close_document(state, index);
+    }
+}
@@ -1,3 +1,21 @@
+pub mod atomic_write;
+pub mod command_palette;
 pub mod commands;
+pub mod encoding;
+pub mod encoding_dialog;
+pub mod file_watch;
+pub mod find_panel;
+pub mod go_to_line_dialog;
+pub mod gutter;
+pub mod highlighter;
+pub mod history;
+pub mod html_export;
+pub mod line_ending;
+pub mod markdown_preview;
 pub mod notepad_screen;
-pub mod menu_topbar;
\ No newline at end of file
+pub mod pdf_export;
+pub mod search;
+pub mod status_bar;
+pub mod syntax;
+pub mod menu_topbar;
+pub mod tab_bar;
\ No newline at end of file
@@ -1,39 +1,257 @@
-use crate::app_state::{AppState, PendingAction};
-use crate::screens::notepad::commands::{new_file, open_file, save};
+use crate::app_state::{AppState, Document, NotepadState, PendingAction, WordWrap};
+use crate::screens::notepad::search::{byte_index, line_char_range};
+use crate::screens::notepad::command_palette::show_command_palette;
+use crate::screens::notepad::commands::{close_document, revert_to_saved, save};
+use crate::screens::notepad::encoding_dialog::show_encoding_dialog;
+use crate::screens::notepad::file_watch::show_external_change_dialog;
+use crate::screens::notepad::find_panel::app_find_panel;
+use crate::screens::notepad::go_to_line_dialog::show_go_to_line_dialog;
+use crate::screens::notepad::gutter::{gutter_width, show_gutter};
+use crate::screens::notepad::highlighter::{highlight, FindMatches};
+use crate::screens::notepad::history::History;
+use crate::screens::notepad::markdown_preview::{app_markdown_preview, top_visible_line};
 use crate::screens::notepad::menu_topbar::app_menu_topbar;
+use crate::screens::notepad::status_bar::app_status_bar;
+use crate::screens::notepad::tab_bar::app_tab_bar;
 use eframe::egui;
+use std::cell::Cell;
+use std::ops::Range;
 
 pub fn notepad_screen(state: &mut AppState, ctx: &egui::Context, frame: &mut eframe::Frame) {
     app_menu_topbar(state, ctx, frame);
+    app_tab_bar(state, ctx, frame);
+    app_find_panel(state, ctx, frame);
+    app_status_bar(state, ctx, frame);
+    app_markdown_preview(state, ctx, frame);
     notepad_content(state, ctx, frame);
 }
 
 fn notepad_content(state: &mut AppState, ctx: &egui::Context, _frame: &mut eframe::Frame) {
     egui::CentralPanel::default().show(ctx, |ui| {
-        egui::ScrollArea::both()
-            .auto_shrink([false; 2])
+        let document = state.notepad_state.active_mut();
+        let document_id = document.id;
+        let pending_scroll_offset = document.pending_scroll_offset.take();
+        let word_wrap = state.settings_state.current.word_wrap;
+
+        // Each tab keeps its own scroll position.
+        let scroll_area = if word_wrap == WordWrap::Window {
+            egui::ScrollArea::vertical()
+        } else {
+            egui::ScrollArea::both()
+        };
+        let mut scroll_area = scroll_area
+            .id_salt(("main_text_editor_scroll", document_id))
+            .auto_shrink([false; 2]);
+        if let Some(offset) = pending_scroll_offset {
+            scroll_area = scroll_area.scroll_offset(offset);
+        }
+
+        let scroll_output = scroll_area
             .show_viewport(ui, |ui, _viewport| {
                 ui.push_id("main_text_editor", |ui| {
-                    let text_edit_response = ui.add(
-                        egui::TextEdit::multiline(&mut state.notepad_state.current_content)
-                            .frame(false)
-                            .desired_width(f32::INFINITY)
-                            .desired_rows(50)
-                            .code_editor(),
-                    );
-
-                    if state.notepad_state.show_save_modal {
-                        text_edit_response.surrender_focus();
+                    // Zoom only scales the editor; the gutter and layout read the same monospace style.
+                    let zoom = state.notepad_state.zoom;
+                    if let Some(font_id) = ui.style_mut().text_styles.get_mut(&egui::TextStyle::Monospace) {
+                        font_id.size *= zoom;
+                    }
+
+                    let undo_limit = state.settings_state.current.undo_limit;
+                    let show_line_numbers = state.settings_state.current.show_line_numbers;
+                    let NotepadState { documents, active_document, find_replace, show_save_modal, show_revert_modal, .. } =
+                        &mut state.notepad_state;
+                    let Document { id, current_content, revision, read_only, language_mode, highlight_cache, history, .. } =
+                        &mut documents[*active_document];
+                    let show_matches = find_replace.visible && !find_replace.matches.is_empty();
+                    let matches = if show_matches { &find_replace.matches[..] } else { &[] };
+
+                    // Wrapping at a column ignores the window width, so long rows scroll instead.
+                    let column_width = {
+                        let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+                        let digit_width = ui.fonts(|fonts| fonts.glyph_width(&font_id, '0'));
+                        (state.settings_state.current.wrap_column.max(1) as f32 + 0.5) * digit_width
+                    };
+
+                    let edited_revision = Cell::new(*revision);
+                    let mut layouter = |ui: &egui::Ui, text: &dyn egui::TextBuffer, window_width: f32| {
+                        let wrap_width = match word_wrap {
+                            WordWrap::Off => f32::INFINITY,
+                            WordWrap::Window => window_width,
+                            WordWrap::Column => column_width,
+                        };
+                        highlight(
+                            ui,
+                            highlight_cache,
+                            *language_mode,
+                            edited_revision.get(),
+                            text.as_str(),
+                            FindMatches { ranges: matches, current: find_replace.current_match },
+                            wrap_width,
+                        )
+                    };
+
+                    // A `&str` buffer can still be selected and copied, but not edited.
+                    let mut read_only_text = current_content.as_str();
+                    let mut editor_buffer;
+                    let text_buffer: &mut dyn egui::TextBuffer = if *read_only {
+                        &mut read_only_text
                     } else {
-                        text_edit_response.request_focus();
+                        editor_buffer = EditorBuffer { text: current_content, revision: &edited_revision, history };
+                        &mut editor_buffer
+                    };
+
+                    let gutter = show_line_numbers
+                        .then(|| gutter_width(ui, text_buffer.as_str().matches('\n').count() + 1));
+                    let current_line_shape = ui.painter().add(egui::Shape::Noop);
+
+                    let text_edit = egui::TextEdit::multiline(text_buffer)
+                        .id_salt(*id)
+                        .frame(false)
+                        .desired_width(f32::INFINITY)
+                        .desired_rows(50)
+                        .code_editor()
+                        .layouter(&mut layouter);
+                    let output = ui
+                        .horizontal_top(|ui| {
+                            ui.spacing_mut().item_spacing.x = 0.0;
+                            if let Some(width) = gutter {
+                                ui.add_space(width);
+                            }
+                            text_edit.show(ui)
+                        })
+                        .inner;
+                    let document = &mut documents[*active_document];
+                    document.revision = edited_revision.get();
+
+                    if let Some(width) = gutter
+                        && let Some(line) = show_gutter(ui, &output, width, current_line_shape)
+                        && let Some(range) = line_char_range(&document.current_content, line)
+                    {
+                        // Include the line break so the whole line is selected.
+                        let total = document.current_content.chars().count();
+                        document.pending_selection = Some(range.start..(range.end + 1).min(total));
+                    }
+
+                    document.history.observe(undo_limit);
+
+                    // The preview is drawn first, so it follows on the next frame.
+                    if document.show_preview {
+                        let line = top_visible_line(&output, ui.clip_rect().top());
+                        if line != document.preview.editor_line {
+                            document.preview.editor_line = line;
+                            ctx.request_repaint();
+                        }
+                    }
+
+                    document.cursor = output.cursor_range;
+                    if let Some(selection) = document.pending_selection.take() {
+                        let range = egui::text::CCursorRange::two(
+                            egui::text::CCursor::new(selection.start),
+                            egui::text::CCursor::new(selection.end),
+                        );
+                        let mut text_edit_state = output.state;
+                        text_edit_state.cursor.set_char_range(Some(range));
+                        text_edit_state.store(ctx, output.response.id);
+                        document.cursor = Some(range);
+
+                        // Only scroll when the selection isn't already on screen.
+                        let cursor_rect = output
+                            .galley
+                            .pos_from_cursor(range.primary)
+                            .translate(output.galley_pos.to_vec2());
+                        if pending_scroll_offset.is_none() && !ui.clip_rect().contains_rect(cursor_rect) {
+                            ui.scroll_to_rect(cursor_rect, Some(egui::Align::Center));
+                        }
+                    }
+
+                    // Only take focus when nothing else (find panel, dialogs) holds it.
+                    if *show_save_modal || *show_revert_modal {
+                        output.response.surrender_focus();
+                    } else if ctx.memory(|memory| memory.focused().is_none()) {
+                        output.response.request_focus();
                     }
                 });
             });
 
+        if let Some(document) = state
+            .notepad_state
+            .documents
+            .iter_mut()
+            .find(|document| document.id == document_id)
+        {
+            document.scroll_offset = scroll_output.state.offset;
+        }
+
         show_unsaved_changes_modal(ctx, state);
+        show_revert_modal(ctx, state);
+        show_encoding_dialog(ctx, state);
+        show_go_to_line_dialog(ctx, state);
+        show_command_palette(ctx, state);
+        show_external_change_dialog(ctx, state);
     });
 }
 
+/// The editable text of the active document, bumping its revision on every edit so the
+/// layouter can tell within the same frame that the text changed, and passing each edit on
+/// to the undo history.
+struct EditorBuffer<'a> {
+    text: &'a mut String,
+    revision: &'a Cell<u64>,
+    history: &'a mut History,
+}
+
+impl egui::TextBuffer for EditorBuffer<'_> {
+    fn is_mutable(&self) -> bool {
+        true
+    }
+
+    fn as_str(&self) -> &str {
+        self.text
+    }
+
+    fn insert_text(&mut self, text: &str, char_index: usize) -> usize {
+        self.revision.set(self.revision.get() + 1);
+        self.history.inserted(byte_index(self.text, char_index), text);
+        self.text.insert_text(text, char_index)
+    }
+
+    fn delete_char_range(&mut self, char_range: Range<usize>) {
+        self.revision.set(self.revision.get() + 1);
+        let start = byte_index(self.text, char_range.start);
+        let end = byte_index(self.text, char_range.end);
+        self.history.removed(start, &self.text[start..end]);
+        self.text.delete_char_range(char_range);
+    }
+
+    fn type_id(&self) -> std::any::TypeId {
+        std::any::TypeId::of::<EditorBuffer<'static>>()
+    }
+}
+
+fn show_revert_modal(ctx: &egui::Context, state: &mut AppState) {
+    if !state.notepad_state.show_revert_modal {
+        return;
+    }
+
+    egui::Window::new(state.text("notepad.revert.dialog.title"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            ui.label(state.text("notepad.revert.dialog.message"));
+
+            ui.horizontal(|ui| {
+                if ui.button(state.text("notepad.unsaved.changes.dialog.button.cancel")).clicked() {
+                    state.notepad_state.show_revert_modal = false;
+                }
+
+                if ui.button(state.text("notepad.revert.dialog.button.revert")).clicked() {
+                    state.notepad_state.show_revert_modal = false;
+                    revert_to_saved(state);
+                }
+            });
+        });
+}
+
 fn show_unsaved_changes_modal(ctx: &egui::Context, state: &mut AppState) {
     if state.notepad_state.show_save_modal {
         egui::Window::new(state.text("notepad.unsaved.changes.dialog.title"))
@@ -46,8 +264,7 @@ fn show_unsaved_changes_modal(ctx: &egui::Context, state: &mut AppState) {
                 ui.horizontal(|ui| {
                     if ui.button(state.text("notepad.unsaved.changes.dialog.button.discard")).clicked() {
                         state.notepad_state.show_save_modal = false;
-                        state.notepad_state.current_content = String::new();
-                        execute_pending_action(ctx, state);
+                        execute_pending_action(ctx, state, true);
                     }
 
                     if ui.button(state.text("notepad.unsaved.changes.dialog.button.cancel")).clicked() {
@@ -58,26 +275,47 @@ fn show_unsaved_changes_modal(ctx: &egui::Context, state: &mut AppState) {
                     if ui.button(state.text("notepad.unsaved.changes.dialog.button.save")).clicked() {
                         state.notepad_state.show_save_modal = false;
                         save(state);
-                        state.notepad_state.current_content = String::new();
-                        execute_pending_action(ctx, state);
+
+                        if state.notepad_state.active().is_dirty() {
+                            state.notepad_state.pending_action = PendingAction::None;
+                        } else {
+                            execute_pending_action(ctx, state, false);
+                        }
                     }
                 });
             });
     }
 
-    fn execute_pending_action(ctx: &egui::Context, state: &mut AppState) {
-        match state.notepad_state.pending_action {
+    /// Carries on with the action that asked about unsaved changes, once the active document
+    /// was saved or, with `discard`, its changes dropped.
+    fn execute_pending_action(ctx: &egui::Context, state: &mut AppState, discard: bool) {
+        match std::mem::replace(&mut state.notepad_state.pending_action, PendingAction::None) {
             PendingAction::None => {}
-            PendingAction::NewFile => {
-                new_file(state);
+            PendingAction::CloseTab(index) => {
+                close_document(state, index);
             }
-            PendingAction::OpenFile => {
-                open_file(state);
-            }
-            PendingAction::CloseApp => {
+            PendingAction::CloseApp { mut discarded } => {
+                if discard {
+                    discarded.push(state.notepad_state.active().id);
+                }
+
+                // Walk the remaining dirty tabs one at a time; nothing is closed before the
+                // last one is answered, so cancelling part way keeps every tab.
+                let keep_untitled = state.settings_state.current.restore_untitled_content;
+                if let Some(index) = state.notepad_state.first_document_blocking_close(keep_untitled, &discarded) {
+                    state.notepad_state.active_document = index;
+                    state.notepad_state.show_save_modal = true;
+                    state.notepad_state.pending_action = PendingAction::CloseApp { discarded };
+                    return;
+                }
+
+                for id in discarded {
+                    if let Some(index) = state.notepad_state.documents.iter().position(|document| document.id == id) {
+                        close_document(state, index);
+                    }
+                }
                 ctx.send_viewport_cmd(egui::ViewportCommand::Close);
             }
         }
-        state.notepad_state.pending_action = PendingAction::None;
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,366 @@
+use printpdf::{Color, IndirectFontRef, Line, Mm, PdfDocument, PdfLayerReference, Point, Pt, Rgb};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+
+const TAB_WIDTH: usize = 4;
+/// Space between rows as a multiple of the font size.
+const LINE_SPACING: f32 = 1.25;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum PaperSize {
+    #[default]
+    A4,
+    A5,
+    Letter,
+    Legal,
+}
+
+impl PaperSize {
+    pub const ALL: [PaperSize; 4] = [PaperSize::A4, PaperSize::A5, PaperSize::Letter, PaperSize::Legal];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PaperSize::A4 => "A4",
+            PaperSize::A5 => "A5",
+            PaperSize::Letter => "Letter",
+            PaperSize::Legal => "Legal",
+        }
+    }
+
+    /// Portrait width and height in millimetres.
+    fn size(&self) -> (f32, f32) {
+        match self {
+            PaperSize::A4 => (210.0, 297.0),
+            PaperSize::A5 => (148.0, 210.0),
+            PaperSize::Letter => (215.9, 279.4),
+            PaperSize::Legal => (215.9, 355.6),
+        }
+    }
+}
+
+/// Everything that shapes the PDF besides the text itself; kept free of UI state so
+/// documents can be rendered without a window.
+pub struct PdfOptions<'a> {
+    /// TrueType font used for all text.
+    pub font: &'a [u8],
+    pub font_size: f32,
+    pub paper_size: PaperSize,
+    pub margin_mm: f32,
+    pub line_numbers: bool,
+    /// Header with `title` and `date`, footer with the page number.
+    pub header_footer: bool,
+    pub title: String,
+    pub date: String,
+    /// Footer text; `{page}` and `{pages}` are replaced.
+    pub page_label: String,
+}
+
+struct Metrics<'a> {
+    face: ttf_parser::Face<'a>,
+    scale: f32,
+}
+
+impl Metrics<'_> {
+    fn char_width(&self, c: char) -> f32 {
+        let advance = self
+            .face
+            .glyph_index(c)
+            .and_then(|glyph| self.face.glyph_hor_advance(glyph))
+            .unwrap_or(self.face.units_per_em() / 2);
+        advance as f32 * self.scale
+    }
+
+    fn text_width(&self, text: &str) -> f32 {
+        text.chars().map(|c| self.char_width(c)).sum()
+    }
+}
+
+/// A printed row: a whole source line or the part of it that fits the page width.
+struct Row {
+    /// Set on the first row of each source line.
+    number: Option<usize>,
+    text: String,
+}
+
+fn expand_tabs(line: &str) -> String {
+    let mut expanded = String::with_capacity(line.len());
+    for c in line.chars() {
+        match c {
+            '\t' => {
+                let spaces = TAB_WIDTH - expanded.chars().count() % TAB_WIDTH;
+                expanded.extend(std::iter::repeat_n(' ', spaces));
+            }
+            c if c.is_control() => {}
+            c => expanded.push(c),
+        }
+    }
+    expanded
+}
+
+/// Splits the text into rows no wider than `width` (in points), breaking after the last
+/// space that fits or mid-word when there is none.
+fn wrap_rows(text: &str, metrics: &Metrics, width: f32) -> Vec<Row> {
+    let mut rows = Vec::new();
+
+    for (index, line) in text.split('\n').enumerate() {
+        let line = expand_tabs(line.trim_end_matches('\r'));
+        let chars: Vec<char> = line.chars().collect();
+        let mut number = Some(index + 1);
+        let mut start = 0;
+
+        loop {
+            let mut end = start;
+            let mut row_width = 0.0;
+            let mut last_space = None;
+            while end < chars.len() {
+                let char_width = metrics.char_width(chars[end]);
+                if row_width + char_width > width && end > start {
+                    break;
+                }
+                if chars[end] == ' ' {
+                    last_space = Some(end);
+                }
+                row_width += char_width;
+                end += 1;
+            }
+
+            if end < chars.len()
+                && let Some(space) = last_space
+            {
+                end = space + 1;
+            }
+
+            rows.push(Row {
+                number: number.take(),
+                text: chars[start..end].iter().collect::<String>().trim_end().to_string(),
+            });
+
+            start = end;
+            if start >= chars.len() {
+                break;
+            }
+        }
+    }
+
+    rows
+}
+
+fn set_color(layer: &PdfLayerReference, gray: f32) {
+    layer.set_fill_color(Color::Rgb(Rgb::new(gray, gray, gray, None)));
+}
+
+/// Lays out `text` on pages and returns the PDF file.
+pub fn render_pdf(text: &str, options: &PdfOptions) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let face = ttf_parser::Face::parse(options.font, 0)?;
+    let scale = options.font_size / face.units_per_em() as f32;
+    let metrics = Metrics { face, scale };
+
+    let (page_width, page_height) = options.paper_size.size();
+    let margin = options.margin_mm.clamp(0.0, page_width.min(page_height) / 3.0);
+    let font_size = options.font_size;
+    let line_height: Mm = Pt(font_size * LINE_SPACING).into();
+    let small_size = font_size * 0.8;
+
+    let line_count = text.split('\n').count();
+    let gutter: Mm = if options.line_numbers {
+        Pt(metrics.text_width(&line_count.to_string()) + metrics.char_width('0') * 2.0).into()
+    } else {
+        Mm(0.0)
+    };
+    let band: Mm = if options.header_footer { Pt(font_size * 2.5).into() } else { Mm(0.0) };
+
+    let text_left = margin + gutter.0;
+    let text_width: Pt = Mm(page_width - margin - text_left).into();
+    let rows = wrap_rows(text, &metrics, text_width.0.max(metrics.char_width('W')));
+
+    let top = page_height - margin - band.0;
+    let bottom = margin + band.0;
+    let rows_per_page = (((top - bottom) / line_height.0).floor() as usize).max(1);
+    let pages = rows.len().div_ceil(rows_per_page).max(1);
+
+    let (document, first_page, first_layer) =
+        PdfDocument::new(&options.title, Mm(page_width), Mm(page_height), "Text");
+    let font: IndirectFontRef = document.add_external_font(Cursor::new(options.font))?;
+
+    for page in 0..pages {
+        let layer = if page == 0 {
+            document.get_page(first_page).get_layer(first_layer)
+        } else {
+            let (page, layer) = document.add_page(Mm(page_width), Mm(page_height), "Text");
+            document.get_page(page).get_layer(layer)
+        };
+
+        if options.header_footer {
+            let header_y = page_height - margin - Mm::from(Pt(small_size)).0;
+            set_color(&layer, 0.35);
+            layer.use_text(&options.title, small_size, Mm(margin), Mm(header_y), &font);
+            let date_width: Mm = Pt(metrics.text_width(&options.date) * 0.8).into();
+            layer.use_text(&options.date, small_size, Mm(page_width - margin - date_width.0), Mm(header_y), &font);
+
+            let rule_y = page_height - margin - band.0 / 2.0;
+            layer.set_outline_color(Color::Rgb(Rgb::new(0.7, 0.7, 0.7, None)));
+            layer.set_outline_thickness(0.5);
+            layer.add_line(Line {
+                points: vec![
+                    (Point::new(Mm(margin), Mm(rule_y)), false),
+                    (Point::new(Mm(page_width - margin), Mm(rule_y)), false),
+                ],
+                is_closed: false,
+            });
+
+            let footer = options
+                .page_label
+                .replace("{page}", &(page + 1).to_string())
+                .replace("{pages}", &pages.to_string());
+            let footer_width: Mm = Pt(metrics.text_width(&footer) * 0.8).into();
+            layer.use_text(&footer, small_size, Mm((page_width - footer_width.0) / 2.0), Mm(margin), &font);
+        }
+
+        let page_rows = rows.iter().skip(page * rows_per_page).take(rows_per_page);
+        for (index, row) in page_rows.enumerate() {
+            // Baseline of the row, one font size below its top.
+            let y = top - index as f32 * line_height.0 - Mm::from(Pt(font_size)).0;
+
+            if options.line_numbers
+                && let Some(number) = row.number
+            {
+                let number = number.to_string();
+                let number_width: Mm = Pt(metrics.text_width(&number) + metrics.char_width('0')).into();
+                set_color(&layer, 0.5);
+                layer.use_text(&number, font_size, Mm(text_left - number_width.0), Mm(y), &font);
+            }
+
+            if !row.text.is_empty() {
+                set_color(&layer, 0.0);
+                layer.use_text(&row.text, font_size, Mm(text_left), Mm(y), &font);
+            }
+        }
+    }
+
+    Ok(document.save_to_bytes()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::font_data;
+    use printpdf::lopdf;
+
+    fn options(line_numbers: bool, header_footer: bool) -> PdfOptions<'static> {
+        PdfOptions {
+            font: font_data("Roboto"),
+            font_size: 12.0,
+            paper_size: PaperSize::A4,
+            margin_mm: 15.0,
+            line_numbers,
+            header_footer,
+            title: "notes.txt".to_string(),
+            date: "2024-01-01".to_string(),
+            page_label: "Page {page} of {pages}".to_string(),
+        }
+    }
+
+    fn numbered_lines(count: usize) -> String {
+        (1..=count).map(|line| format!("line {}", line)).collect::<Vec<_>>().join("\n")
+    }
+
+    /// Number of text objects drawn on each page.
+    fn texts_per_page(bytes: &[u8]) -> Vec<usize> {
+        let document = lopdf::Document::load_mem(bytes).unwrap();
+        document
+            .get_pages()
+            .values()
+            .map(|page| {
+                let content = document.get_page_content(*page).unwrap();
+                let content = lopdf::content::Content::decode(&content).unwrap();
+                content.operations.iter().filter(|operation| operation.operator == "BT").count()
+            })
+            .collect()
+    }
+
+    fn metrics() -> Metrics<'static> {
+        let face = ttf_parser::Face::parse(font_data("Roboto"), 0).unwrap();
+        let scale = 12.0 / face.units_per_em() as f32;
+        Metrics { face, scale }
+    }
+
+    #[test]
+    fn renders_a_pdf_file() {
+        let bytes = render_pdf("hello", &options(false, false)).unwrap();
+        assert!(bytes.starts_with(b"%PDF"));
+        assert_eq!(texts_per_page(&bytes), vec![1]);
+    }
+
+    #[test]
+    fn empty_document_has_one_page() {
+        let bytes = render_pdf("", &options(false, true)).unwrap();
+        assert_eq!(texts_per_page(&bytes).len(), 1);
+    }
+
+    #[test]
+    fn long_documents_flow_onto_more_pages() {
+        let lines = 200;
+        let bytes = render_pdf(&numbered_lines(lines), &options(false, false)).unwrap();
+        let pages = texts_per_page(&bytes);
+        let rows_per_page = pages[0];
+
+        assert!(pages.len() > 1);
+        assert_eq!(pages.len(), lines.div_ceil(rows_per_page));
+        assert!(pages.iter().all(|texts| *texts <= rows_per_page));
+        assert_eq!(pages.iter().sum::<usize>(), lines);
+    }
+
+    #[test]
+    fn line_numbers_add_one_text_per_line() {
+        let text = numbered_lines(10);
+        let without = texts_per_page(&render_pdf(&text, &options(false, false)).unwrap());
+        let with = texts_per_page(&render_pdf(&text, &options(true, false)).unwrap());
+        assert_eq!(without, vec![10]);
+        assert_eq!(with, vec![20]);
+    }
+
+    #[test]
+    fn header_and_footer_are_drawn_on_every_page() {
+        let text = numbered_lines(200);
+        let without = texts_per_page(&render_pdf(&text, &options(false, false)).unwrap());
+        let with = texts_per_page(&render_pdf(&text, &options(false, true)).unwrap());
+
+        // The header and footer take room from the text, so pages hold fewer rows.
+        assert!(with.len() >= without.len());
+        assert_eq!(with.iter().sum::<usize>(), 200 + 3 * with.len());
+    }
+
+    #[test]
+    fn wrap_rows_breaks_after_the_last_space_that_fits() {
+        let metrics = metrics();
+        let line = "alpha beta gamma delta epsilon zeta eta theta";
+        let width = metrics.text_width("alpha beta gamma");
+        let rows = wrap_rows(line, &metrics, width);
+
+        assert!(rows.len() > 1);
+        assert_eq!(rows[0].number, Some(1));
+        assert!(rows[1..].iter().all(|row| row.number.is_none()));
+        assert!(rows.iter().all(|row| metrics.text_width(&row.text) <= width));
+        let joined: Vec<&str> = rows.iter().map(|row| row.text.as_str()).collect();
+        assert_eq!(joined.join(" "), line);
+    }
+
+    #[test]
+    fn wrap_rows_splits_words_longer_than_the_page() {
+        let metrics = metrics();
+        let line = "x".repeat(100);
+        let width = metrics.text_width(&"x".repeat(30));
+        let rows = wrap_rows(&line, &metrics, width);
+
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows.iter().map(|row| row.text.as_str()).collect::<String>(), line);
+    }
+
+    #[test]
+    fn wrap_rows_numbers_source_lines() {
+        let rows = wrap_rows("a\n\tb\r\nc", &metrics(), 500.0);
+        let numbers: Vec<Option<usize>> = rows.iter().map(|row| row.number).collect();
+        assert_eq!(numbers, vec![Some(1), Some(2), Some(3)]);
+        assert_eq!(rows[1].text, "    b");
+    }
+}
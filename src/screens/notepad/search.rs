@@ -0,0 +1,239 @@
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+
+#[derive(Clone, Default, PartialEq)]
+pub struct SearchOptions {
+    pub match_case: bool,
+    pub whole_word: bool,
+    pub use_regex: bool,
+}
+
+pub fn build_regex(query: &str, options: &SearchOptions) -> Result<Regex, regex::Error> {
+    let pattern = if options.use_regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+
+    // Half boundaries only ask for no word character right outside the match, so queries
+    // that start or end with punctuation (`-v`, `x++`) still count as whole words.
+    let pattern = if options.whole_word {
+        format!(r"\b{{start-half}}(?:{pattern})\b{{end-half}}")
+    } else {
+        pattern
+    };
+
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!options.match_case)
+        .multi_line(true)
+        .build()
+}
+
+/// Byte ranges of every non-empty match in `text`.
+pub fn find_matches(text: &str, regex: &Regex) -> Vec<Range<usize>> {
+    regex
+        .find_iter(text)
+        .filter(|found| !found.is_empty())
+        .map(|found| found.range())
+        .collect()
+}
+
+/// Text that replaces the match at `range`, expanding `$1`/`${name}` groups in regex mode.
+fn expand_replacement(
+    text: &str,
+    regex: &Regex,
+    range: &Range<usize>,
+    replacement: &str,
+    options: &SearchOptions,
+) -> String {
+    if !options.use_regex {
+        return replacement.to_string();
+    }
+
+    let mut expanded = String::new();
+    if let Some(captures) = regex.captures_at(text, range.start) {
+        captures.expand(replacement, &mut expanded);
+    }
+    expanded
+}
+
+pub fn replace_match(
+    text: &str,
+    regex: &Regex,
+    range: &Range<usize>,
+    replacement: &str,
+    options: &SearchOptions,
+) -> String {
+    let expanded = expand_replacement(text, regex, range, replacement, options);
+
+    let mut result = String::with_capacity(text.len() + expanded.len());
+    result.push_str(&text[..range.start]);
+    result.push_str(&expanded);
+    result.push_str(&text[range.end..]);
+    result
+}
+
+/// Returns the new text and the number of replacements made.
+pub fn replace_all(
+    text: &str,
+    regex: &Regex,
+    replacement: &str,
+    options: &SearchOptions,
+) -> (String, usize) {
+    let matches = find_matches(text, regex);
+
+    let mut result = String::with_capacity(text.len());
+    let mut last_end = 0;
+    for range in &matches {
+        result.push_str(&text[last_end..range.start]);
+        result.push_str(&expand_replacement(text, regex, range, replacement, options));
+        last_end = range.end;
+    }
+    result.push_str(&text[last_end..]);
+
+    (result, matches.len())
+}
+
+pub fn char_index(text: &str, byte_index: usize) -> usize {
+    text[..byte_index].chars().count()
+}
+
+pub fn byte_index(text: &str, char_index: usize) -> usize {
+    text.char_indices()
+        .nth(char_index)
+        .map_or(text.len(), |(index, _)| index)
+}
+
+/// Char range of the given 1-based line, without its line break.
+pub fn line_char_range(text: &str, line: usize) -> Option<Range<usize>> {
+    let mut start = 0;
+    for (index, content) in text.split('\n').enumerate() {
+        let len = content.trim_end_matches('\r').chars().count();
+        if index + 1 == line {
+            return Some(start..start + len);
+        }
+        start += content.chars().count() + 1;
+    }
+    None
+}
+
+/// 1-based line and column of the char index `cursor`.
+pub fn line_column(text: &str, cursor: usize) -> (usize, usize) {
+    let mut line = 1;
+    let mut column = 1;
+    for c in text.chars().take(cursor) {
+        if c == '\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += 1;
+        }
+    }
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(match_case: bool, whole_word: bool, use_regex: bool) -> SearchOptions {
+        SearchOptions {
+            match_case,
+            whole_word,
+            use_regex,
+        }
+    }
+
+    fn matched<'a>(text: &'a str, query: &str, options: &SearchOptions) -> Vec<&'a str> {
+        let regex = build_regex(query, options).unwrap();
+        find_matches(text, &regex).into_iter().map(|range| &text[range]).collect()
+    }
+
+    #[test]
+    fn plain_queries_are_literal_and_ignore_case() {
+        let options = options(false, false, false);
+        assert_eq!(matched("a.b axb A.B", "a.b", &options), ["a.b", "A.B"]);
+    }
+
+    #[test]
+    fn match_case_is_exact() {
+        assert_eq!(matched("Word word WORD", "word", &options(true, false, false)), ["word"]);
+    }
+
+    #[test]
+    fn whole_word_skips_matches_inside_words() {
+        let options = options(false, true, false);
+        assert_eq!(matched("cat concat cats cat", "cat", &options), ["cat", "cat"]);
+    }
+
+    #[test]
+    fn whole_word_accepts_queries_starting_or_ending_with_punctuation() {
+        let options = options(false, true, false);
+        assert_eq!(matched("ls -v --verbose", "-v", &options), ["-v"]);
+        assert_eq!(matched("x++; y++", "x++", &options), ["x++"]);
+        assert_eq!(matched("(a) (ab)", "(a)", &options), ["(a)"]);
+        assert!(matched("ax++", "x++", &options).is_empty());
+    }
+
+    #[test]
+    fn whole_word_wraps_regex_alternatives() {
+        let options = options(false, true, true);
+        assert_eq!(matched("cat dog catdog", "cat|dog", &options), ["cat", "dog"]);
+    }
+
+    #[test]
+    fn invalid_regex_is_an_error() {
+        assert!(build_regex("(", &options(false, false, true)).is_err());
+        assert!(build_regex("(", &options(false, false, false)).is_ok());
+    }
+
+    #[test]
+    fn replace_all_expands_groups_in_regex_mode() {
+        let options = options(true, false, true);
+        let regex = build_regex(r"(\w+)@(\w+)", &options).unwrap();
+        assert_eq!(
+            replace_all("a@b c@d", &regex, "$2@$1", &options),
+            ("b@a d@c".to_string(), 2)
+        );
+    }
+
+    #[test]
+    fn replace_all_keeps_dollars_in_plain_mode() {
+        let options = options(true, false, false);
+        let regex = build_regex("x", &options).unwrap();
+        assert_eq!(replace_all("x x", &regex, "$1", &options), ("$1 $1".to_string(), 2));
+    }
+
+    #[test]
+    fn replace_match_replaces_only_that_range() {
+        let options = options(true, false, false);
+        let regex = build_regex("a", &options).unwrap();
+        assert_eq!(replace_match("aaa", &regex, &(1..2), "b", &options), "aba");
+    }
+
+    #[test]
+    fn char_and_byte_indices_round_trip() {
+        let text = "héllo wörld";
+        assert_eq!(byte_index(text, 2), 3);
+        assert_eq!(char_index(text, 3), 2);
+        assert_eq!(byte_index(text, 100), text.len());
+    }
+
+    #[test]
+    fn line_char_range_excludes_the_line_break() {
+        let text = "one\r\ntwo\nthree";
+        assert_eq!(line_char_range(text, 1), Some(0..3));
+        assert_eq!(line_char_range(text, 2), Some(5..8));
+        assert_eq!(line_char_range(text, 3), Some(9..14));
+        assert_eq!(line_char_range(text, 4), None);
+    }
+
+    #[test]
+    fn line_column_counts_from_one() {
+        let text = "ab\ncd";
+        assert_eq!(line_column(text, 0), (1, 1));
+        assert_eq!(line_column(text, 2), (1, 3));
+        assert_eq!(line_column(text, 3), (2, 1));
+        assert_eq!(line_column(text, 5), (2, 3));
+    }
+}
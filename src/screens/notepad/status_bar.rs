@@ -0,0 +1,197 @@
+use crate::app_state::{AppState, Document, NotepadState};
+use crate::screens::notepad::commands::convert_line_endings;
+use crate::screens::notepad::line_ending::LineEnding;
+use crate::screens::notepad::menu_topbar::{
+    on_go_to_line_button_clicked, on_language_mode_selected, on_reopen_with_encoding_clicked,
+    on_save_with_encoding_clicked, on_zoom_reset_clicked,
+};
+use crate::screens::notepad::search::{byte_index, line_column};
+use crate::screens::notepad::syntax::LanguageMode;
+use eframe::egui;
+use std::ops::Range;
+
+/// Words and lines of the whole document. Only ASCII whitespace separates words, so this
+/// scans bytes instead of decoding chars; it still reads the whole text, hence `StatusCounts`.
+fn word_and_line_count(text: &str) -> (usize, usize) {
+    let mut words = 0;
+    let mut in_word = false;
+    for byte in text.bytes() {
+        let is_space = byte.is_ascii_whitespace();
+        if !is_space && !in_word {
+            words += 1;
+        }
+        in_word = !is_space;
+    }
+    (words, text.bytes().filter(|byte| *byte == b'\n').count() + 1)
+}
+
+/// Counts shown in the status bar, recomputed only when the text, cursor or selection moved
+/// instead of rescanning the document every frame.
+#[derive(Default)]
+pub struct StatusCounts {
+    /// Document id and revision the totals were counted for.
+    totals_key: Option<(u64, u64)>,
+    words: usize,
+    lines: usize,
+    /// Document id, revision, cursor and selection the position was computed for.
+    position_key: Option<(u64, u64, usize, Range<usize>)>,
+    position: (usize, usize),
+    selected_lines: usize,
+}
+
+impl StatusCounts {
+    fn update(&mut self, document: &Document) {
+        let text = &document.current_content;
+
+        let totals_key = Some((document.id, document.revision));
+        if self.totals_key != totals_key {
+            (self.words, self.lines) = word_and_line_count(text);
+            self.totals_key = totals_key;
+        }
+
+        let cursor = document.cursor.map_or(0, |range| range.primary.index);
+        let selection = document.selection();
+        let position_key = Some((document.id, document.revision, cursor, selection.clone()));
+        if self.position_key != position_key {
+            self.position = line_column(text, cursor);
+            let selected = &text[byte_index(text, selection.start)..byte_index(text, selection.end)];
+            self.selected_lines = selected.bytes().filter(|byte| *byte == b'\n').count() + 1;
+            self.position_key = position_key;
+        }
+    }
+}
+
+fn selection_summary(state: &AppState, document: &Document) -> Option<String> {
+    let selection = document.selection();
+    if selection.is_empty() {
+        return None;
+    }
+
+    Some(
+        state
+            .text("notepad.status.selection")
+            .replace("{chars}", &selection.len().to_string())
+            .replace("{lines}", &state.notepad_state.status_counts.selected_lines.to_string()),
+    )
+}
+
+pub fn app_status_bar(state: &mut AppState, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+        ui.add_enabled_ui(!state.notepad_state.show_save_modal, |ui| {
+            ui.horizontal(|ui| {
+                let NotepadState { documents, active_document, status_counts, .. } = &mut state.notepad_state;
+                status_counts.update(&documents[*active_document]);
+                let (line, column) = status_counts.position;
+                let position = state
+                    .text("notepad.status.position")
+                    .replace("{line}", &line.to_string())
+                    .replace("{column}", &column.to_string());
+                if ui
+                    .add(egui::Button::new(position).frame(false))
+                    .on_hover_text(state.text("notepad.menu.edit.go.to.line"))
+                    .clicked()
+                {
+                    on_go_to_line_button_clicked(state);
+                }
+
+                let document = state.notepad_state.active();
+                if let Some(summary) = selection_summary(state, document) {
+                    ui.separator();
+                    ui.label(summary);
+                }
+
+                ui.separator();
+                let StatusCounts { words, lines, .. } = state.notepad_state.status_counts;
+                ui.label(
+                    state
+                        .text("notepad.status.totals")
+                        .replace("{words}", &words.to_string())
+                        .replace("{lines}", &lines.to_string()),
+                );
+
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    status_segments(state, ui);
+                });
+            });
+        });
+    });
+}
+
+/// Right-hand segments, laid out right to left; each opens the matching command.
+fn status_segments(state: &mut AppState, ui: &mut egui::Ui) {
+    let zoom = format!("{}%", (state.notepad_state.zoom * 100.0).round());
+    if ui
+        .add(egui::Button::new(zoom).frame(false))
+        .on_hover_text(state.text("notepad.menu.view.zoom.reset"))
+        .clicked()
+    {
+        on_zoom_reset_clicked(state);
+    }
+    ui.separator();
+
+    let document = state.notepad_state.active();
+
+    if document.is_dirty() {
+        ui.label(state.text("notepad.status.modified"));
+        ui.separator();
+    }
+
+    if document.read_only {
+        ui.label(state.text("notepad.status.readonly"));
+        ui.separator();
+    }
+
+    let language_mode = document.language_mode;
+    ui.menu_button(language_mode.label(), |ui| {
+        for option in LanguageMode::ALL {
+            if ui.radio(language_mode == option, option.label()).clicked() {
+                on_language_mode_selected(state, option);
+                ui.close();
+            }
+        }
+    })
+    .response
+    .on_hover_text(state.text("notepad.menu.language.mode"));
+
+    ui.separator();
+
+    let document = state.notepad_state.active();
+    let line_ending = document.line_ending;
+    let mixed = document.mixed_line_endings;
+    ui.menu_button(line_ending.label(), |ui| {
+        ui.label(state.text("notepad.menu.edit.line.endings"));
+        ui.separator();
+        for option in LineEnding::ALL {
+            if ui.radio(line_ending == option, option.label()).clicked() {
+                convert_line_endings(state, option);
+                ui.close();
+            }
+        }
+    })
+    .response
+    .on_hover_text(state.text("notepad.menu.edit.line.endings"));
+
+    if mixed {
+        ui.colored_label(ui.visuals().warn_fg_color, state.text("notepad.line.endings.mixed"))
+            .on_hover_text(state.text("notepad.line.endings.mixed.hint"));
+    }
+
+    ui.separator();
+
+    let document = state.notepad_state.active();
+    let has_path = document.current_file_path.is_some();
+    ui.menu_button(document.encoding.label(), |ui| {
+        if ui
+            .add_enabled(has_path, egui::Button::new(state.text("notepad.menu.file.reopen.encoding")))
+            .clicked()
+        {
+            on_reopen_with_encoding_clicked(state);
+            ui.close();
+        }
+
+        if ui.button(state.text("notepad.menu.file.save.encoding")).clicked() {
+            on_save_with_encoding_clicked(state);
+            ui.close();
+        }
+    });
+}
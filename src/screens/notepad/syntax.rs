@@ -0,0 +1,823 @@
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LanguageMode {
+    #[default]
+    PlainText,
+    Rust,
+    Markdown,
+    Json,
+    Toml,
+    Yaml,
+    Shell,
+    Python,
+    Ini,
+}
+
+impl LanguageMode {
+    pub const ALL: [LanguageMode; 9] = [
+        LanguageMode::PlainText,
+        LanguageMode::Rust,
+        LanguageMode::Markdown,
+        LanguageMode::Json,
+        LanguageMode::Toml,
+        LanguageMode::Yaml,
+        LanguageMode::Shell,
+        LanguageMode::Python,
+        LanguageMode::Ini,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LanguageMode::PlainText => "Plain Text",
+            LanguageMode::Rust => "Rust",
+            LanguageMode::Markdown => "Markdown",
+            LanguageMode::Json => "JSON",
+            LanguageMode::Toml => "TOML",
+            LanguageMode::Yaml => "YAML",
+            LanguageMode::Shell => "Shell",
+            LanguageMode::Python => "Python",
+            LanguageMode::Ini => "INI",
+        }
+    }
+
+    /// Picks the language from the file name, falling back to a `#!` line.
+    pub fn detect(path: Option<&Path>, content: &str) -> LanguageMode {
+        let by_name = path.map_or(LanguageMode::PlainText, LanguageMode::from_path);
+        if by_name != LanguageMode::PlainText {
+            return by_name;
+        }
+
+        let first_line = content.lines().next().unwrap_or_default();
+        if let Some(interpreter) = first_line.strip_prefix("#!") {
+            if interpreter.contains("python") {
+                return LanguageMode::Python;
+            }
+            if ["sh", "bash", "zsh", "ksh", "dash"]
+                .iter()
+                .any(|shell| interpreter.split(['/', ' ']).any(|part| part == *shell))
+            {
+                return LanguageMode::Shell;
+            }
+        }
+
+        LanguageMode::PlainText
+    }
+
+    fn from_path(path: &Path) -> LanguageMode {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        match name.as_str() {
+            "cargo.lock" | "pipfile" => return LanguageMode::Toml,
+            ".bashrc" | ".bash_profile" | ".profile" | ".zshrc" | ".zprofile" => return LanguageMode::Shell,
+            ".gitconfig" | ".editorconfig" | ".npmrc" => return LanguageMode::Ini,
+            _ => {}
+        }
+
+        let extension = name.rsplit_once('.').map(|(_, extension)| extension).unwrap_or_default();
+        match extension {
+            "rs" => LanguageMode::Rust,
+            "md" | "markdown" | "mdown" => LanguageMode::Markdown,
+            "json" | "jsonc" | "geojson" => LanguageMode::Json,
+            "toml" => LanguageMode::Toml,
+            "yaml" | "yml" => LanguageMode::Yaml,
+            "sh" | "bash" | "zsh" | "ksh" => LanguageMode::Shell,
+            "py" | "pyw" | "pyi" => LanguageMode::Python,
+            "ini" | "cfg" | "conf" | "properties" | "desktop" => LanguageMode::Ini,
+            _ => LanguageMode::PlainText,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    Keyword,
+    Literal,
+    String,
+    Number,
+    Comment,
+    Key,
+    Variable,
+    Heading,
+    Emphasis,
+    Link,
+}
+
+#[derive(Clone, Debug)]
+pub struct Span {
+    pub range: Range<usize>,
+    pub kind: TokenKind,
+}
+
+/// What is still open at the end of a line: a comment, string or code block.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum LineState {
+    #[default]
+    Normal,
+    BlockComment(u8),
+    String(u8),
+    TripleString(u8),
+    FencedCode,
+}
+
+/// Tokens of a single line (byte ranges within it) and the state the next line starts in.
+pub fn highlight_line(language_mode: LanguageMode, line: &str, state: LineState) -> (Vec<Span>, LineState) {
+    let mut spans = Vec::new();
+    let state = match language_mode {
+        LanguageMode::PlainText => LineState::Normal,
+        LanguageMode::Rust => lex_code(&RUST, line, 0, state, &mut spans),
+        LanguageMode::Python => lex_code(&PYTHON, line, 0, state, &mut spans),
+        LanguageMode::Shell => lex_code(&SHELL, line, 0, state, &mut spans),
+        LanguageMode::Json => lex_json(line, state, &mut spans),
+        LanguageMode::Toml => lex_toml(line, state, &mut spans),
+        LanguageMode::Yaml => lex_yaml(line, state, &mut spans),
+        LanguageMode::Ini => lex_ini(line, &mut spans),
+        LanguageMode::Markdown => lex_markdown(line, state, &mut spans),
+    };
+    (spans, state)
+}
+
+struct CodeSpec {
+    line_comments: &'static [&'static str],
+    /// `#` only starts a comment at the start of a line or after whitespace.
+    comment_needs_space: bool,
+    block_comment: Option<(&'static str, &'static str)>,
+    nested_comments: bool,
+    quotes: &'static [u8],
+    /// Plain quoted strings may continue on the next line.
+    multiline_strings: bool,
+    triple_quotes: bool,
+    /// `'a'` is a char and `'a` a lifetime.
+    char_literals: bool,
+    variables: bool,
+    keywords: &'static [&'static str],
+    literals: &'static [&'static str],
+}
+
+const RUST: CodeSpec = CodeSpec {
+    line_comments: &["//"],
+    comment_needs_space: false,
+    block_comment: Some(("/*", "*/")),
+    nested_comments: true,
+    quotes: b"\"'",
+    multiline_strings: true,
+    triple_quotes: false,
+    char_literals: true,
+    variables: false,
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+        "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "type",
+        "unsafe", "use", "where", "while", "yield",
+    ],
+    literals: &["true", "false", "None", "Some", "Ok", "Err"],
+};
+
+const PYTHON: CodeSpec = CodeSpec {
+    line_comments: &["#"],
+    comment_needs_space: false,
+    block_comment: None,
+    nested_comments: false,
+    quotes: b"\"'",
+    multiline_strings: false,
+    triple_quotes: true,
+    char_literals: false,
+    variables: false,
+    keywords: &[
+        "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+        "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is",
+        "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with",
+        "yield", "self",
+    ],
+    literals: &["True", "False", "None"],
+};
+
+const SHELL: CodeSpec = CodeSpec {
+    line_comments: &["#"],
+    comment_needs_space: true,
+    block_comment: None,
+    nested_comments: false,
+    quotes: b"\"'`",
+    multiline_strings: true,
+    triple_quotes: false,
+    char_literals: false,
+    variables: true,
+    keywords: &[
+        "if", "then", "else", "elif", "fi", "case", "esac", "for", "select", "while", "until",
+        "do", "done", "in", "function", "time", "return", "exit", "local", "export", "readonly",
+        "declare", "source", "alias", "unset", "shift", "break", "continue",
+    ],
+    literals: &["true", "false"],
+};
+
+const JSON: CodeSpec = CodeSpec {
+    line_comments: &["//"],
+    comment_needs_space: false,
+    block_comment: Some(("/*", "*/")),
+    nested_comments: false,
+    quotes: b"\"",
+    multiline_strings: false,
+    triple_quotes: false,
+    char_literals: false,
+    variables: false,
+    keywords: &[],
+    literals: &["true", "false", "null"],
+};
+
+const TOML: CodeSpec = CodeSpec {
+    line_comments: &["#"],
+    comment_needs_space: false,
+    block_comment: None,
+    nested_comments: false,
+    quotes: b"\"'",
+    multiline_strings: false,
+    triple_quotes: true,
+    char_literals: false,
+    variables: false,
+    keywords: &[],
+    literals: &["true", "false", "inf", "nan"],
+};
+
+const YAML: CodeSpec = CodeSpec {
+    line_comments: &["#"],
+    comment_needs_space: true,
+    block_comment: None,
+    nested_comments: false,
+    quotes: b"\"'",
+    multiline_strings: true,
+    triple_quotes: false,
+    char_literals: false,
+    variables: false,
+    keywords: &[],
+    literals: &["true", "false", "null", "yes", "no", "on", "off", "~"],
+};
+
+const INI_VALUE: CodeSpec = CodeSpec {
+    line_comments: &[],
+    comment_needs_space: false,
+    block_comment: None,
+    nested_comments: false,
+    quotes: b"\"",
+    multiline_strings: false,
+    triple_quotes: false,
+    char_literals: false,
+    variables: false,
+    keywords: &[],
+    literals: &["true", "false", "yes", "no", "on", "off"],
+};
+
+fn push(spans: &mut Vec<Span>, range: Range<usize>, kind: TokenKind) {
+    if !range.is_empty() {
+        spans.push(Span { range, kind });
+    }
+}
+
+fn is_ident_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || byte >= 0x80
+}
+
+fn scan_while(bytes: &[u8], mut i: usize, predicate: impl Fn(u8) -> bool) -> usize {
+    while i < bytes.len() && predicate(bytes[i]) {
+        i += 1;
+    }
+    i
+}
+
+/// End of the string starting after its opening quote, or `None` if it continues past the line.
+fn find_string_end(bytes: &[u8], mut i: usize, quote: u8, triple: bool) -> Option<usize> {
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            byte if byte == quote => {
+                if !triple {
+                    return Some(i + 1);
+                }
+                if bytes[i..].starts_with(&[quote, quote, quote]) {
+                    return Some(i + 3);
+                }
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Scans a block comment, returning where it stops and how many levels are still open.
+fn scan_block_comment(spec: &CodeSpec, bytes: &[u8], mut i: usize, mut depth: u8) -> (usize, u8) {
+    let Some((open, close)) = spec.block_comment else {
+        return (bytes.len(), 0);
+    };
+
+    while i < bytes.len() {
+        if bytes[i..].starts_with(close.as_bytes()) {
+            i += close.len();
+            depth -= 1;
+            if depth == 0 {
+                return (i, 0);
+            }
+        } else if spec.nested_comments && bytes[i..].starts_with(open.as_bytes()) {
+            i += open.len();
+            depth = depth.saturating_add(1);
+        } else {
+            i += 1;
+        }
+    }
+    (bytes.len(), depth)
+}
+
+fn is_char_literal(line: &str, i: usize) -> bool {
+    let rest = &line[i + 1..];
+    if rest.starts_with('\\') {
+        return true;
+    }
+    rest.chars()
+        .next()
+        .is_some_and(|c| rest[c.len_utf8()..].starts_with('\''))
+}
+
+fn lex_code(spec: &CodeSpec, line: &str, mut i: usize, mut state: LineState, spans: &mut Vec<Span>) -> LineState {
+    let bytes = line.as_bytes();
+
+    // Finish what the previous line left open.
+    match state {
+        LineState::BlockComment(depth) => {
+            let (end, depth) = scan_block_comment(spec, bytes, i, depth);
+            push(spans, i..end, TokenKind::Comment);
+            if depth > 0 {
+                return LineState::BlockComment(depth);
+            }
+            i = end;
+        }
+        LineState::String(quote) | LineState::TripleString(quote) => {
+            let triple = matches!(state, LineState::TripleString(_));
+            match find_string_end(bytes, i, quote, triple) {
+                Some(end) => {
+                    push(spans, i..end, TokenKind::String);
+                    i = end;
+                }
+                None => {
+                    push(spans, i..bytes.len(), TokenKind::String);
+                    return state;
+                }
+            }
+        }
+        LineState::Normal | LineState::FencedCode => {}
+    }
+    state = LineState::Normal;
+
+    while i < bytes.len() {
+        let byte = bytes[i];
+        let rest = &bytes[i..];
+
+        let comment_allowed = !spec.comment_needs_space || i == 0 || bytes[i - 1].is_ascii_whitespace();
+        if comment_allowed && spec.line_comments.iter().any(|comment| rest.starts_with(comment.as_bytes())) {
+            push(spans, i..bytes.len(), TokenKind::Comment);
+            return state;
+        }
+
+        if let Some((open, _)) = spec.block_comment
+            && rest.starts_with(open.as_bytes())
+        {
+            let (end, depth) = scan_block_comment(spec, bytes, i + open.len(), 1);
+            push(spans, i..end, TokenKind::Comment);
+            if depth > 0 {
+                return LineState::BlockComment(depth);
+            }
+            i = end;
+            continue;
+        }
+
+        if spec.quotes.contains(&byte) {
+            if spec.char_literals && byte == b'\'' && !is_char_literal(line, i) {
+                let end = scan_while(bytes, i + 1, is_ident_byte);
+                push(spans, i..end, TokenKind::Variable);
+                i = end;
+                continue;
+            }
+
+            let triple = spec.triple_quotes && rest.starts_with(&[byte, byte, byte]);
+            let open_len = if triple { 3 } else { 1 };
+            match find_string_end(bytes, i + open_len, byte, triple) {
+                Some(end) => {
+                    push(spans, i..end, TokenKind::String);
+                    i = end;
+                }
+                None => {
+                    push(spans, i..bytes.len(), TokenKind::String);
+                    return if triple {
+                        LineState::TripleString(byte)
+                    } else if spec.multiline_strings {
+                        LineState::String(byte)
+                    } else {
+                        LineState::Normal
+                    };
+                }
+            }
+            continue;
+        }
+
+        if spec.variables && byte == b'$' {
+            let end = if rest.starts_with(b"${") {
+                bytes[i..].iter().position(|b| *b == b'}').map_or(bytes.len(), |end| i + end + 1)
+            } else {
+                scan_while(bytes, i + 1, is_ident_byte).max(i + 2).min(bytes.len())
+            };
+            push(spans, i..end, TokenKind::Variable);
+            i = end;
+            continue;
+        }
+
+        if byte.is_ascii_digit() {
+            if i > 0 && is_ident_byte(bytes[i - 1]) {
+                i += 1;
+                continue;
+            }
+            let end = scan_while(bytes, i + 1, |b| b.is_ascii_alphanumeric() || b == b'.' || b == b'_');
+            push(spans, i..end, TokenKind::Number);
+            i = end;
+            continue;
+        }
+
+        if is_ident_byte(byte) || byte == b'~' {
+            let end = if byte == b'~' { i + 1 } else { scan_while(bytes, i, is_ident_byte) };
+            let word = &line[i..end];
+            if spec.keywords.contains(&word) {
+                push(spans, i..end, TokenKind::Keyword);
+            } else if spec.literals.contains(&word) {
+                push(spans, i..end, TokenKind::Literal);
+            } else if spec.char_literals && rest.get(word.len()) == Some(&b'!') {
+                // Rust macro invocation.
+                push(spans, i..end + 1, TokenKind::Keyword);
+                i = end + 1;
+                continue;
+            }
+            i = end;
+            continue;
+        }
+
+        if spec.char_literals && (rest.starts_with(b"#[") || rest.starts_with(b"#![")) {
+            let end = bytes[i..].iter().position(|b| *b == b']').map_or(bytes.len(), |end| i + end + 1);
+            push(spans, i..end, TokenKind::Variable);
+            i = end;
+            continue;
+        }
+
+        i += 1;
+    }
+
+    state
+}
+
+fn lex_json(line: &str, state: LineState, spans: &mut Vec<Span>) -> LineState {
+    let state = lex_code(&JSON, line, 0, state, spans);
+
+    // A string followed by `:` is an object key.
+    let bytes = line.as_bytes();
+    for span in spans.iter_mut() {
+        if span.kind == TokenKind::String {
+            let next = scan_while(bytes, span.range.end, |b| b.is_ascii_whitespace());
+            if bytes.get(next) == Some(&b':') {
+                span.kind = TokenKind::Key;
+            }
+        }
+    }
+
+    state
+}
+
+/// `[section]` / `[[array]]` headers; returns where the header ends.
+fn section_header(line: &str, spans: &mut Vec<Span>) -> Option<usize> {
+    let start = line.len() - line.trim_start().len();
+    if !line[start..].starts_with('[') {
+        return None;
+    }
+
+    let end = line[start..].rfind(']').map_or(line.len(), |end| start + end + 1);
+    push(spans, start..end, TokenKind::Heading);
+    Some(end)
+}
+
+/// Length of a bare or quoted key before `separator`, if the line starts with one.
+fn leading_key(line: &str, separators: &[u8]) -> Option<Range<usize>> {
+    let bytes = line.as_bytes();
+    let start = scan_while(bytes, 0, |b| b == b' ' || b == b'\t');
+    let mut i = start;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' | b'\'' => i = find_string_end(bytes, i + 1, bytes[i], false)?,
+            byte if separators.contains(&byte) => {
+                let end = start + line[start..i].trim_end().len();
+                return (end > start).then_some(start..end);
+            }
+            byte if is_ident_byte(byte) || b"-. ".contains(&byte) => i += 1,
+            _ => return None,
+        }
+    }
+    None
+}
+
+fn lex_toml(line: &str, state: LineState, spans: &mut Vec<Span>) -> LineState {
+    if state != LineState::Normal {
+        return lex_code(&TOML, line, 0, state, spans);
+    }
+
+    if let Some(end) = section_header(line, spans) {
+        return lex_code(&TOML, line, end, state, spans);
+    }
+
+    let mut start = 0;
+    if let Some(key) = leading_key(line, b"=") {
+        start = key.end;
+        push(spans, key, TokenKind::Key);
+    }
+    lex_code(&TOML, line, start, state, spans)
+}
+
+fn lex_yaml(line: &str, state: LineState, spans: &mut Vec<Span>) -> LineState {
+    if state != LineState::Normal {
+        return lex_code(&YAML, line, 0, state, spans);
+    }
+
+    let trimmed = line.trim_end();
+    if trimmed == "---" || trimmed == "..." {
+        push(spans, 0..trimmed.len(), TokenKind::Keyword);
+        return state;
+    }
+
+    // List item markers, possibly nested: `- - key: value`.
+    let bytes = line.as_bytes();
+    let mut start = scan_while(bytes, 0, |b| b == b' ' || b == b'\t');
+    while bytes[start..].starts_with(b"- ") || bytes[start..] == *b"-" {
+        push(spans, start..start + 1, TokenKind::Keyword);
+        start = scan_while(bytes, start + 1, |b| b == b' ');
+    }
+
+    if let Some(key) = leading_key(&line[start..], b":") {
+        let key = start + key.start..start + key.end;
+        let after = bytes.get(key.end + 1);
+        if after.is_none() || after.is_some_and(u8::is_ascii_whitespace) {
+            start = key.end;
+            push(spans, key, TokenKind::Key);
+        }
+    }
+
+    lex_code(&YAML, line, start, state, spans)
+}
+
+fn lex_ini(line: &str, spans: &mut Vec<Span>) -> LineState {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with(';') || trimmed.starts_with('#') {
+        push(spans, line.len() - trimmed.len()..line.len(), TokenKind::Comment);
+        return LineState::Normal;
+    }
+
+    if section_header(line, spans).is_some() {
+        return LineState::Normal;
+    }
+
+    let mut start = 0;
+    if let Some(key) = leading_key(line, b"=:") {
+        start = key.end;
+        push(spans, key, TokenKind::Key);
+    }
+    lex_code(&INI_VALUE, line, start, LineState::Normal, spans)
+}
+
+fn lex_markdown(line: &str, state: LineState, spans: &mut Vec<Span>) -> LineState {
+    let indent = line.len() - line.trim_start().len();
+    let trimmed = line.trim();
+    let is_fence = trimmed.starts_with("```") || trimmed.starts_with("~~~");
+
+    if state == LineState::FencedCode {
+        if is_fence {
+            push(spans, indent..line.len(), TokenKind::Keyword);
+            return LineState::Normal;
+        }
+        push(spans, 0..line.len(), TokenKind::String);
+        return state;
+    }
+
+    if is_fence {
+        push(spans, indent..line.len(), TokenKind::Keyword);
+        return LineState::FencedCode;
+    }
+
+    let hashes = trimmed.bytes().take_while(|b| *b == b'#').count();
+    if (1..=6).contains(&hashes) && trimmed.as_bytes().get(hashes).is_none_or(|b| *b == b' ') {
+        push(spans, indent..line.len(), TokenKind::Heading);
+        return state;
+    }
+
+    if trimmed.starts_with('>') {
+        push(spans, indent..line.len(), TokenKind::Comment);
+        return state;
+    }
+
+    let compact: String = trimmed.chars().filter(|c| !c.is_whitespace()).collect();
+    if compact.len() >= 3 && ['-', '*', '_'].iter().any(|rule| compact.chars().all(|c| c == *rule)) {
+        push(spans, indent..line.len(), TokenKind::Keyword);
+        return state;
+    }
+
+    let bytes = line.as_bytes();
+    let mut i = indent;
+    if trimmed.starts_with("- ") || trimmed.starts_with("* ") || trimmed.starts_with("+ ") {
+        push(spans, i..i + 1, TokenKind::Keyword);
+        i += 2;
+    } else {
+        let digits = scan_while(bytes, i, |b| b.is_ascii_digit());
+        if digits > i && matches!(bytes.get(digits), Some(b'.') | Some(b')')) && bytes.get(digits + 1) == Some(&b' ') {
+            push(spans, i..digits + 1, TokenKind::Keyword);
+            i = digits + 2;
+        }
+    }
+
+    lex_markdown_inline(line, i, spans);
+    state
+}
+
+fn lex_markdown_inline(line: &str, mut i: usize, spans: &mut Vec<Span>) {
+    let bytes = line.as_bytes();
+
+    let find = |from: usize, pattern: &[u8]| {
+        bytes[from.min(bytes.len())..]
+            .windows(pattern.len())
+            .position(|window| window == pattern)
+            .map(|offset| from + offset)
+    };
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'`' => {
+                let ticks = scan_while(bytes, i, |b| b == b'`') - i;
+                let fence = &bytes[i..i + ticks];
+                match find(i + ticks, fence) {
+                    Some(end) => {
+                        push(spans, i..end + ticks, TokenKind::String);
+                        i = end + ticks;
+                    }
+                    None => i += ticks,
+                }
+            }
+            marker @ (b'*' | b'_') => {
+                let strong = bytes.get(i + 1) == Some(&marker);
+                let delimiter: &[u8] = if strong { &bytes[i..i + 2] } else { &bytes[i..i + 1] };
+                let opens = bytes.get(i + delimiter.len()).is_some_and(|b| !b.is_ascii_whitespace());
+                let word_inside = marker == b'_' && i > 0 && is_ident_byte(bytes[i - 1]);
+                match find(i + delimiter.len(), delimiter) {
+                    Some(end) if opens && !word_inside && end > i + delimiter.len() => {
+                        push(spans, i..end + delimiter.len(), TokenKind::Emphasis);
+                        i = end + delimiter.len();
+                    }
+                    _ => i += delimiter.len(),
+                }
+            }
+            b'[' | b'!' if bytes[i..].starts_with(b"[") || bytes[i..].starts_with(b"![") => {
+                let label_start = if bytes[i] == b'!' { i + 1 } else { i };
+                let link_end = find(label_start, b"](")
+                    .and_then(|close| find(close + 2, b")").map(|end| end + 1));
+                match link_end {
+                    Some(end) => {
+                        push(spans, i..end, TokenKind::Link);
+                        i = end;
+                    }
+                    None => i += 1,
+                }
+            }
+            b'<' if bytes[i..].starts_with(b"<http") => match find(i, b">") {
+                Some(end) => {
+                    push(spans, i..end + 1, TokenKind::Link);
+                    i = end + 1;
+                }
+                None => i += 1,
+            },
+            _ => i += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Text and kind of every token in `line`, plus the state the next line starts in.
+    fn tokens(language_mode: LanguageMode, line: &str, state: LineState) -> (Vec<(&str, TokenKind)>, LineState) {
+        let (spans, end_state) = highlight_line(language_mode, line, state);
+        let tokens = spans.into_iter().map(|span| (&line[span.range], span.kind)).collect();
+        (tokens, end_state)
+    }
+
+    fn kind_of(language_mode: LanguageMode, line: &str, text: &str) -> Option<TokenKind> {
+        tokens(language_mode, line, LineState::Normal)
+            .0
+            .into_iter()
+            .find(|(token, _)| *token == text)
+            .map(|(_, kind)| kind)
+    }
+
+    #[test]
+    fn detects_languages_from_file_names() {
+        let detect = |name: &str| LanguageMode::detect(Some(Path::new(name)), "");
+        assert_eq!(detect("src/main.rs"), LanguageMode::Rust);
+        assert_eq!(detect("README.MD"), LanguageMode::Markdown);
+        assert_eq!(detect("Cargo.lock"), LanguageMode::Toml);
+        assert_eq!(detect(".bashrc"), LanguageMode::Shell);
+        assert_eq!(detect("config.yml"), LanguageMode::Yaml);
+        assert_eq!(detect("notes.txt"), LanguageMode::PlainText);
+    }
+
+    #[test]
+    fn detects_scripts_from_the_shebang() {
+        assert_eq!(LanguageMode::detect(None, "#!/usr/bin/env python3\n"), LanguageMode::Python);
+        assert_eq!(LanguageMode::detect(None, "#!/bin/bash -e\n"), LanguageMode::Shell);
+        assert_eq!(LanguageMode::detect(None, "#!/usr/bin/env bashful\n"), LanguageMode::PlainText);
+        assert_eq!(LanguageMode::detect(Some(Path::new("x.rs")), "#!/bin/sh"), LanguageMode::Rust);
+    }
+
+    #[test]
+    fn rust_tokens() {
+        let line = r#"let count = 42; // done "#;
+        assert_eq!(kind_of(LanguageMode::Rust, line, "let"), Some(TokenKind::Keyword));
+        assert_eq!(kind_of(LanguageMode::Rust, line, "42"), Some(TokenKind::Number));
+        assert_eq!(kind_of(LanguageMode::Rust, line, "// done "), Some(TokenKind::Comment));
+        assert_eq!(kind_of(LanguageMode::Rust, line, "count"), None);
+
+        let line = r#"let s = "a \" b";"#;
+        assert_eq!(kind_of(LanguageMode::Rust, line, r#""a \" b""#), Some(TokenKind::String));
+    }
+
+    #[test]
+    fn block_comments_carry_over_to_the_next_line() {
+        let (_, state) = tokens(LanguageMode::Rust, "let a = 1; /* start", LineState::Normal);
+        assert_ne!(state, LineState::Normal);
+
+        let (spans, state) = tokens(LanguageMode::Rust, "still */ let", state);
+        assert_eq!(spans[0], ("still */", TokenKind::Comment));
+        assert_eq!(spans[1], ("let", TokenKind::Keyword));
+        assert_eq!(state, LineState::Normal);
+    }
+
+    #[test]
+    fn python_triple_strings_span_lines() {
+        let (_, state) = tokens(LanguageMode::Python, r#"doc = """first"#, LineState::Normal);
+        assert_ne!(state, LineState::Normal);
+
+        let (spans, state) = tokens(LanguageMode::Python, r#"second""" + x"#, state);
+        assert_eq!(spans[0], (r#"second""""#, TokenKind::String));
+        assert_eq!(state, LineState::Normal);
+    }
+
+    #[test]
+    fn json_keys_and_values() {
+        let line = r#"  "name": "value", "count": 3, "ok": true"#;
+        assert_eq!(kind_of(LanguageMode::Json, line, r#""name""#), Some(TokenKind::Key));
+        assert_eq!(kind_of(LanguageMode::Json, line, r#""value""#), Some(TokenKind::String));
+        assert_eq!(kind_of(LanguageMode::Json, line, "3"), Some(TokenKind::Number));
+        assert_eq!(kind_of(LanguageMode::Json, line, "true"), Some(TokenKind::Literal));
+    }
+
+    #[test]
+    fn shell_variables_and_comments() {
+        let line = "echo $HOME # home";
+        assert_eq!(kind_of(LanguageMode::Shell, line, "$HOME"), Some(TokenKind::Variable));
+        assert_eq!(kind_of(LanguageMode::Shell, line, "# home"), Some(TokenKind::Comment));
+    }
+
+    #[test]
+    fn markdown_headings_and_fences() {
+        assert_eq!(kind_of(LanguageMode::Markdown, "# Title", "# Title"), Some(TokenKind::Heading));
+
+        let (_, state) = tokens(LanguageMode::Markdown, "```rust", LineState::Normal);
+        assert_eq!(state, LineState::FencedCode);
+        let (_, state) = tokens(LanguageMode::Markdown, "# not a heading", state);
+        assert_eq!(state, LineState::FencedCode);
+        let (_, state) = tokens(LanguageMode::Markdown, "```", state);
+        assert_eq!(state, LineState::Normal);
+    }
+
+    #[test]
+    fn plain_text_has_no_tokens() {
+        let (spans, state) = tokens(LanguageMode::PlainText, "let x = \"1\"; // no", LineState::Normal);
+        assert!(spans.is_empty());
+        assert_eq!(state, LineState::Normal);
+    }
+
+    #[test]
+    fn spans_stay_on_char_boundaries() {
+        for language_mode in LanguageMode::ALL {
+            let line = "é \"ü\" // ß # ñ [ç](x) `ø` *å* 1é";
+            let (spans, _) = highlight_line(language_mode, line, LineState::Normal);
+            for span in spans {
+                assert!(line.is_char_boundary(span.range.start) && line.is_char_boundary(span.range.end));
+            }
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use crate::app_state::AppState;
+use crate::screens::notepad::menu_topbar::on_close_tab_clicked;
+use eframe::egui;
+
+pub fn app_tab_bar(state: &mut AppState, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    egui::TopBottomPanel::top("tab_bar").show(ctx, |ui| {
+        ui.add_enabled_ui(!state.notepad_state.show_save_modal, |ui| {
+            egui::ScrollArea::horizontal().show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    let untitled = state.text("notepad.tab.untitled");
+                    let read_only = state.text("notepad.tab.readonly");
+                    let mut select_index = None;
+                    let mut close_index = None;
+
+                    for (index, document) in state.notepad_state.documents.iter().enumerate() {
+                        let mut title = document.title(&untitled);
+                        if document.read_only {
+                            title = format!("{} {}", title, read_only);
+                        }
+                        if document.is_dirty() {
+                            title.push_str(" ●");
+                        }
+
+                        let is_active = index == state.notepad_state.active_document;
+                        let tab = ui.selectable_label(is_active, title);
+                        if let Some(path) = &document.current_file_path {
+                            tab.clone().on_hover_text(path.to_string_lossy());
+                        }
+                        if tab.clicked() {
+                            select_index = Some(index);
+                        }
+                        // Draw the button first; a middle click must not skip drawing it.
+                        let close_clicked = ui.small_button("×").clicked();
+                        if tab.middle_clicked() || close_clicked {
+                            close_index = Some(index);
+                        }
+
+                        ui.separator();
+                    }
+
+                    if let Some(index) = select_index {
+                        state.notepad_state.active_document = index;
+                    }
+
+                    if let Some(index) = close_index {
+                        on_close_tab_clicked(state, index);
+                    }
+                });
+            });
+        });
+    });
+}
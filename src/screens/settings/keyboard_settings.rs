@@ -0,0 +1,96 @@
+use crate::app_state::AppState;
+use crate::keybindings::{finish_recording, format_sequence, Action, Keybindings, Recording};
+use eframe::egui;
+
+/// Keyboard section of the settings: edits `keybindings_state.unsaved`, which is written to
+/// `keybindings.json` together with the other settings.
+pub fn keyboard_settings(state: &mut AppState, ui: &mut egui::Ui) {
+    ui.group(|ui| {
+        ui.set_width(ui.available_width());
+        ui.label(state.text("settings.keyboard"));
+        ui.add_space(5.0);
+
+        egui::Grid::new("keybindings_grid")
+            .num_columns(2)
+            .spacing([20.0, 6.0])
+            .striped(true)
+            .show(ui, |ui| {
+                for action in Action::ALL {
+                    ui.label(action.label(state));
+                    ui.horizontal_wrapped(|ui| {
+                        action_bindings(state, ui, action);
+                    });
+                    ui.end_row();
+                }
+            });
+
+        ui.add_space(5.0);
+        if ui.button(state.text("settings.keyboard.reset")).clicked() {
+            state.keybindings_state.unsaved = Keybindings::default();
+            state.keybindings_state.recording = None;
+        }
+    });
+}
+
+fn action_bindings(state: &mut AppState, ui: &mut egui::Ui, action: Action) {
+    let keybindings = &state.keybindings_state.unsaved;
+    let mut removed = None;
+
+    for (index, sequence) in keybindings.sequences(action).iter().enumerate() {
+        let conflicts = keybindings.conflicts(action, sequence);
+        let mut text = egui::RichText::new(format_sequence(sequence)).monospace();
+        if !conflicts.is_empty() {
+            text = text.color(ui.visuals().error_fg_color);
+        }
+
+        let mut response = ui.add(egui::Button::new(text).small());
+        if !conflicts.is_empty() {
+            let names: Vec<String> = conflicts
+                .iter()
+                .map(|other| other.label(state))
+                .collect();
+            response = response.on_hover_text(
+                state
+                    .text("settings.keyboard.conflict")
+                    .replace("{actions}", &names.join(", ")),
+            );
+        }
+        if response.on_hover_text(state.text("settings.keyboard.remove")).clicked() {
+            removed = Some(index);
+        }
+    }
+
+    if let Some(index) = removed
+        && let Some(sequences) = state.keybindings_state.unsaved.bindings.get_mut(&action)
+    {
+        sequences.remove(index);
+    }
+
+    match &state.keybindings_state.recording {
+        Some(recording) if recording.action == action => {
+            let typed = if recording.sequence.is_empty() {
+                state.text("settings.keyboard.recording")
+            } else {
+                format_sequence(&recording.sequence)
+            };
+            ui.colored_label(ui.visuals().warn_fg_color, typed);
+
+            // A single chord is kept with Done; a second one finishes on its own.
+            if ui.small_button(state.text("settings.keyboard.done")).clicked() {
+                finish_recording(&mut state.keybindings_state);
+            }
+        }
+        _ => {
+            if ui
+                .small_button("+")
+                .on_hover_text(state.text("settings.keyboard.add"))
+                .clicked()
+            {
+                state.keybindings_state.recording = Some(Recording {
+                    action,
+                    sequence: Vec::new(),
+                });
+            }
+        }
+    }
+}
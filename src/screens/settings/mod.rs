@@ -1 +1,2 @@
+pub mod keyboard_settings;
 pub mod settings_screen;
\ No newline at end of file
@@ -1,5 +1,10 @@
 use crate::app_state::{get_available_fonts, AppState, Language};
+use crate::keybindings::{finish_recording, save_keybindings};
 use crate::navigator::Screen;
+use crate::screens::settings::keyboard_settings::keyboard_settings;
+use crate::screens::notepad::encoding::AVAILABLE_ENCODINGS;
+use crate::screens::notepad::line_ending::LineEnding;
+use crate::screens::notepad::pdf_export::PaperSize;
 use eframe::egui;
 use rfd::FileDialog;
 
@@ -82,14 +87,13 @@ fn settings_content(state: &mut AppState, ctx: &egui::Context, _frame: &mut efra
                             ui.text_edit_singleline(
                                 &mut state.settings_state.unsaved.default_path,
                             );
-                            if ui.button(state.text("settings.default.path.button")).clicked() {
-                                if let Some(path) = FileDialog::new()
+                            if ui.button(state.text("settings.default.path.button")).clicked()
+                                && let Some(path) = FileDialog::new()
                                     .set_title(state.text("settings.default.path.select.folder"))
                                     .pick_folder()
-                                {
-                                    state.settings_state.unsaved.default_path =
-                                        path.to_string_lossy().into_owned();
-                                }
+                            {
+                                state.settings_state.unsaved.default_path =
+                                    path.to_string_lossy().into_owned();
                             }
                         });
                     });
@@ -126,6 +130,149 @@ fn settings_content(state: &mut AppState, ctx: &egui::Context, _frame: &mut efra
 
                     ui.add_space(10.0);
 
+                    // Default encoding for new files
+                    ui.group(|ui| {
+                        ui.set_width(ui.available_width());
+                        ui.label(state.text("settings.default.encoding"));
+                        egui::ComboBox::from_id_salt("DefaultEncoding")
+                            .selected_text(&state.settings_state.unsaved.default_encoding)
+                            .show_ui(ui, |ui| {
+                                for encoding in AVAILABLE_ENCODINGS {
+                                    ui.selectable_value(
+                                        &mut state.settings_state.unsaved.default_encoding,
+                                        encoding.name().to_string(),
+                                        encoding.name(),
+                                    );
+                                }
+                            });
+                    });
+
+                    ui.add_space(10.0);
+
+                    // Default line ending for new files
+                    ui.group(|ui| {
+                        ui.set_width(ui.available_width());
+                        ui.label(state.text("settings.default.line.ending"));
+                        ui.horizontal(|ui| {
+                            for line_ending in LineEnding::ALL {
+                                ui.radio_value(
+                                    &mut state.settings_state.unsaved.default_line_ending,
+                                    line_ending,
+                                    line_ending.label(),
+                                );
+                            }
+                        });
+                    });
+
+                    ui.add_space(10.0);
+
+                    // Backup copies
+                    ui.group(|ui| {
+                        ui.set_width(ui.available_width());
+                        ui.label(state.text("settings.backup.count"));
+                        let unit_text = state.text("settings.backup.count.unit");
+                        ui.add(
+                            egui::Slider::new(
+                                &mut state.settings_state.unsaved.backup_count,
+                                0..=10,
+                            )
+                            .text(unit_text),
+                        );
+                    });
+
+                    ui.add_space(10.0);
+
+                    // Printing and PDF export
+                    ui.group(|ui| {
+                        ui.set_width(ui.available_width());
+                        ui.label(state.text("settings.print"));
+                        ui.horizontal(|ui| {
+                            ui.label(state.text("settings.print.paper"));
+                            egui::ComboBox::from_id_salt("PaperSize")
+                                .selected_text(state.settings_state.unsaved.paper_size.label())
+                                .show_ui(ui, |ui| {
+                                    for paper_size in PaperSize::ALL {
+                                        ui.selectable_value(
+                                            &mut state.settings_state.unsaved.paper_size,
+                                            paper_size,
+                                            paper_size.label(),
+                                        );
+                                    }
+                                });
+                        });
+                        ui.label(state.text("settings.print.margin"));
+                        ui.add(
+                            egui::Slider::new(
+                                &mut state.settings_state.unsaved.print_margin_mm,
+                                0.0..=50.0,
+                            )
+                            .text("mm"),
+                        );
+                        let line_numbers_text = state.text("settings.print.line.numbers");
+                        ui.checkbox(
+                            &mut state.settings_state.unsaved.print_line_numbers,
+                            line_numbers_text,
+                        );
+                        let header_footer_text = state.text("settings.print.header.footer");
+                        ui.checkbox(
+                            &mut state.settings_state.unsaved.print_header_footer,
+                            header_footer_text,
+                        );
+                    });
+
+                    ui.add_space(10.0);
+
+                    // Recent files
+                    ui.group(|ui| {
+                        ui.set_width(ui.available_width());
+                        ui.label(state.text("settings.recent.files.limit"));
+                        let unit_text = state.text("settings.recent.files.limit.unit");
+                        ui.add(
+                            egui::Slider::new(
+                                &mut state.settings_state.unsaved.recent_files_limit,
+                                0..=30,
+                            )
+                            .text(unit_text),
+                        );
+                    });
+
+                    ui.add_space(10.0);
+
+                    // Autosave for crash recovery
+                    ui.group(|ui| {
+                        ui.set_width(ui.available_width());
+                        let autosave_text = state.text("settings.autosave");
+                        ui.checkbox(&mut state.settings_state.unsaved.autosave_enabled, autosave_text);
+                        ui.label(state.text("settings.autosave.interval"));
+                        let unit_text = state.text("settings.autosave.interval.unit");
+                        ui.add_enabled(
+                            state.settings_state.unsaved.autosave_enabled,
+                            egui::Slider::new(
+                                &mut state.settings_state.unsaved.autosave_interval_secs,
+                                5..=600,
+                            )
+                            .text(unit_text),
+                        );
+                    });
+
+                    ui.add_space(10.0);
+
+                    // Undo history
+                    ui.group(|ui| {
+                        ui.set_width(ui.available_width());
+                        ui.label(state.text("settings.undo.limit"));
+                        let unit_text = state.text("settings.undo.limit.unit");
+                        ui.add(
+                            egui::Slider::new(
+                                &mut state.settings_state.unsaved.undo_limit,
+                                10..=1000,
+                            )
+                            .text(unit_text),
+                        );
+                    });
+
+                    ui.add_space(10.0);
+
                     // Confirm before close
                     let checkbox_text = state.text("settings.close.confirmation");
                     ui.checkbox(
@@ -133,16 +280,55 @@ fn settings_content(state: &mut AppState, ctx: &egui::Context, _frame: &mut efra
                         checkbox_text,
                     );
 
+                    // Line number gutter
+                    let checkbox_text = state.text("settings.line.numbers");
+                    ui.checkbox(
+                        &mut state.settings_state.unsaved.show_line_numbers,
+                        checkbox_text,
+                    );
+
+                    // Keep untitled documents between sessions
+                    let checkbox_text = state.text("settings.restore.untitled");
+                    ui.checkbox(
+                        &mut state.settings_state.unsaved.restore_untitled_content,
+                        checkbox_text,
+                    );
+
+                    // Reuse the running window for files opened from the command line
+                    let checkbox_text = state.text("settings.single.instance");
+                    ui.checkbox(
+                        &mut state.settings_state.unsaved.single_instance,
+                        checkbox_text,
+                    );
+
+                    // Reload files changed by other programs
+                    let checkbox_text = state.text("settings.auto.reload");
+                    ui.checkbox(
+                        &mut state.settings_state.unsaved.auto_reload_unmodified,
+                        checkbox_text,
+                    );
+
+                    ui.add_space(10.0);
+
+                    keyboard_settings(state, ui);
+
                     // Bottom buttons
                     ui.add_space(20.0);
+                    let conflicts = state.keybindings_state.unsaved.has_conflicts();
                     ui.with_layout(egui::Layout::bottom_up(egui::Align::RIGHT), |ui| {
                         ui.horizontal(|ui| {
-                            if ui.button(state.text("settings.button.save")).clicked() {
-                                save(state);
+                            if ui
+                                .add_enabled(!conflicts, egui::Button::new(state.text("settings.button.save")))
+                                .clicked()
+                                && save(state)
+                            {
                                 state.screen = Screen::Notepad;
                             }
 
-                            if ui.button(state.text("settings.button.apply")).clicked() {
+                            if ui
+                                .add_enabled(!conflicts, egui::Button::new(state.text("settings.button.apply")))
+                                .clicked()
+                            {
                                 save(state);
                             }
 
@@ -151,15 +337,30 @@ fn settings_content(state: &mut AppState, ctx: &egui::Context, _frame: &mut efra
                                 state.screen = Screen::Notepad;
                             }
                         });
+
+                        if conflicts {
+                            ui.colored_label(ui.visuals().error_fg_color, state.text("settings.keyboard.conflicts"));
+                        }
                     });
                 });
             });
     });
 }
 
-fn save(state: &mut AppState) {
+/// Applies the settings; returns `false` and changes nothing while shortcuts conflict.
+fn save(state: &mut AppState) -> bool {
+    finish_recording(&mut state.keybindings_state);
+    if state.keybindings_state.unsaved.has_conflicts() {
+        return false;
+    }
+
     state.settings_state.current = state.settings_state.unsaved.clone();
 
+    if state.keybindings_state.current != state.keybindings_state.unsaved {
+        state.keybindings_state.current = state.keybindings_state.unsaved.clone();
+        save_keybindings(state);
+    }
+
     if let Err(err) = state.save_settings_to_disk() {
         eprintln!("ERRO: saving configuration -> {}", err);
     }
@@ -167,10 +368,14 @@ fn save(state: &mut AppState) {
     if let Err(e) = state.load_language_strings() {
         println!("ERROR: loading translations -> {}", e);
     }
+
+    true
 }
 
-fn discard_changes(state: &mut AppState) {
-    state.settings_state.unsaved = state.settings_state.current.clone()
+pub fn discard_changes(state: &mut AppState) {
+    state.settings_state.unsaved = state.settings_state.current.clone();
+    state.keybindings_state.unsaved = state.keybindings_state.current.clone();
+    state.keybindings_state.recording = None;
 }
 
 fn get_language(state: &AppState, language: &Language) -> String {
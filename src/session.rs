@@ -0,0 +1,215 @@
+use crate::app_state::{AppState, Document};
+use crate::keybindings::Action;
+use crate::navigator::Screen;
+use crate::recovery::discard_recovery;
+use crate::screens::find_in_files::find_in_files_screen::prepare_find_in_files;
+use crate::screens::notepad::commands::{reload_from_disk, set_zoom};
+use crate::screens::notepad::encoding::TextEncoding;
+use crate::screens::notepad::line_ending::LineEnding;
+use crate::screens::notepad::syntax::LanguageMode;
+use eframe::egui;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+const SESSION_FILE: &str = "session.json";
+
+/// What was open when the app last closed.
+#[derive(Serialize, Deserialize)]
+struct Session {
+    screen: Screen,
+    active_document: usize,
+    documents: Vec<SessionDocument>,
+    #[serde(default = "default_zoom")]
+    zoom: f32,
+    /// Ids of the commands last run from the palette, most recent first.
+    #[serde(default)]
+    recent_actions: Vec<String>,
+}
+
+fn default_zoom() -> f32 {
+    1.0
+}
+
+#[derive(Serialize, Deserialize)]
+struct SessionDocument {
+    file_path: Option<PathBuf>,
+    /// Text of an untitled document, only kept when the user opted in.
+    content: Option<String>,
+    encoding: String,
+    bom: bool,
+    line_ending: LineEnding,
+    #[serde(default)]
+    language_mode: LanguageMode,
+    /// Selection as char indices, anchor first.
+    selection: (usize, usize),
+    scroll_offset: (f32, f32),
+    #[serde(default)]
+    show_preview: bool,
+}
+
+impl SessionDocument {
+    fn from_document(document: &Document, keep_untitled: bool) -> Option<Self> {
+        let content = match &document.current_file_path {
+            Some(_) => None,
+            None if keep_untitled && !document.current_content.is_empty() => {
+                Some(document.current_content.clone())
+            }
+            None => return None,
+        };
+
+        // Tabs that were never shown since they were restored still hold their position as
+        // pending, to be applied on their first frame.
+        let selection = match (&document.pending_selection, document.cursor) {
+            (Some(range), _) => (range.start, range.end),
+            (None, Some(range)) => (range.secondary.index, range.primary.index),
+            (None, None) => (0, 0),
+        };
+        let scroll_offset = document.pending_scroll_offset.unwrap_or(document.scroll_offset);
+
+        Some(Self {
+            file_path: document.current_file_path.clone(),
+            content,
+            encoding: document.encoding.encoding.name().to_string(),
+            bom: document.encoding.bom,
+            line_ending: document.line_ending,
+            language_mode: document.language_mode,
+            selection,
+            scroll_offset: (scroll_offset.x, scroll_offset.y),
+            show_preview: document.show_preview,
+        })
+    }
+
+    fn into_document(self) -> Option<Document> {
+        let encoding = TextEncoding {
+            bom: self.bom,
+            ..TextEncoding::from_name(&self.encoding)
+        };
+
+        let mut document = Document {
+            encoding,
+            line_ending: self.line_ending,
+            language_mode: self.language_mode,
+            show_preview: self.show_preview,
+            ..Default::default()
+        };
+
+        if let Some(path) = self.file_path {
+            document.current_file_path = Some(path);
+            if let Err(e) = reload_from_disk(&mut document, encoding.encoding) {
+                info!("Not restoring {:?} -> {}", document.current_file_path, e);
+                return None;
+            }
+        } else {
+            let content = self.content?;
+            document.set_content(content);
+        }
+
+        let len = document.current_content.chars().count();
+        let (anchor, cursor) = self.selection;
+        document.pending_selection = Some(anchor.min(len)..cursor.min(len));
+        document.pending_scroll_offset = Some(egui::vec2(self.scroll_offset.0, self.scroll_offset.1));
+
+        Some(document)
+    }
+}
+
+fn session_path(state: &AppState) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(state.app_config_dir()?.join(SESSION_FILE))
+}
+
+/// Remembers the open documents and the current screen for the next start.
+pub fn save_session(state: &AppState) {
+    let keep_untitled = state.settings_state.current.restore_untitled_content;
+    let mut active_document = 0;
+    let mut documents = Vec::new();
+
+    for (index, document) in state.notepad_state.documents.iter().enumerate() {
+        if let Some(session_document) = SessionDocument::from_document(document, keep_untitled) {
+            if index == state.notepad_state.active_document {
+                active_document = documents.len();
+            }
+            documents.push(session_document);
+        }
+    }
+
+    let session = Session {
+        screen: state.screen,
+        active_document,
+        documents,
+        zoom: state.notepad_state.zoom,
+        recent_actions: state
+            .notepad_state
+            .recent_actions
+            .iter()
+            .map(|action| action.id().to_string())
+            .collect(),
+    };
+
+    let result = session_path(state).and_then(|path| {
+        fs::create_dir_all(path.parent().unwrap_or(&path))?;
+        fs::write(path, serde_json::to_string_pretty(&session)?)?;
+        Ok(())
+    });
+
+    if let Err(e) = result {
+        error!("ERROR: saving session -> {}", e);
+    }
+}
+
+/// Untitled buffers kept in the session don't need a recovery copy as well.
+pub fn discard_session_recovery(state: &AppState) {
+    if !state.settings_state.current.restore_untitled_content {
+        return;
+    }
+
+    for document in &state.notepad_state.documents {
+        if document.current_file_path.is_none() {
+            discard_recovery(state, document);
+        }
+    }
+}
+
+/// Reopens the documents of the previous session, skipping files that can't be read anymore.
+pub fn restore_session(state: &mut AppState) {
+    let Ok(json) = session_path(state).and_then(|path| Ok(fs::read_to_string(path)?)) else {
+        return;
+    };
+
+    let session: Session = match serde_json::from_str(&json) {
+        Ok(session) => session,
+        Err(e) => {
+            error!("ERROR: reading session -> {}", e);
+            return;
+        }
+    };
+
+    let mut active_document = 0;
+    let mut documents = Vec::new();
+
+    for (index, session_document) in session.documents.into_iter().enumerate() {
+        if let Some(document) = session_document.into_document() {
+            if index <= session.active_document {
+                active_document = documents.len();
+            }
+            documents.push(document);
+        }
+    }
+
+    if !documents.is_empty() {
+        state.notepad_state.documents = documents;
+        state.notepad_state.active_document = active_document;
+    }
+
+    set_zoom(state, session.zoom);
+    state.notepad_state.recent_actions = session
+        .recent_actions
+        .iter()
+        .filter_map(|id| Action::from_id(id))
+        .collect();
+    state.screen = session.screen;
+    if state.screen == Screen::FindInFiles {
+        prepare_find_in_files(state);
+    }
+}
@@ -1,39 +1,107 @@
-use crate::app_state::AppState;
+use crate::app_state::{AppState, WordWrap};
+use crate::keybindings::{capture_recording, take_triggered_actions, Action};
 use crate::navigator::Screen;
-use crate::screens::notepad::menu_topbar::{on_new_button_clicked, on_open_button_clicked, on_save_button_clicked};
+use crate::screens::notepad::commands::{close_find, convert_line_endings, set_zoom};
+use crate::screens::notepad::line_ending::LineEnding;
+use crate::screens::notepad::menu_topbar::{
+    on_close_tab_clicked, on_command_palette_clicked, on_copy_as_html_clicked,
+    on_export_html_clicked, on_export_pdf_clicked, on_find_button_clicked,
+    on_find_in_files_button_clicked, on_go_to_line_button_clicked, on_line_numbers_toggled,
+    on_markdown_preview_toggled, on_new_button_clicked, on_open_button_clicked, on_print_clicked,
+    on_redo_button_clicked, on_reopen_with_encoding_clicked, on_replace_button_clicked,
+    on_revert_button_clicked, on_save_as_button_clicked, on_save_button_clicked,
+    on_save_copy_button_clicked, on_save_with_encoding_clicked, on_undo_button_clicked,
+    on_word_wrap_selected, on_zoom_in_clicked, on_zoom_out_clicked, on_zoom_reset_clicked,
+};
+use crate::screens::settings::settings_screen::discard_changes;
 use eframe::egui;
 
 pub fn shortcuts(ctx: &egui::Context, state: &mut AppState) {
-    let modifier = get_modifier(ctx);
+    // A multi-key binding started in the editor doesn't carry over to another screen.
+    if !matches!(state.screen, Screen::Notepad) {
+        state.keybindings_state.pending.clear();
+    }
 
     match state.screen {
         Screen::Notepad => {
-            if ctx.input(|i| i.key_pressed(egui::Key::N) && modifier) {
-                on_new_button_clicked(state);
+            for action in take_triggered_actions(ctx, &mut state.keybindings_state) {
+                run_action(state, action);
             }
 
-            if ctx.input(|i| i.key_pressed(egui::Key::O) && modifier) {
-                on_open_button_clicked(state);
+            if state.notepad_state.find_replace.visible
+                && state.notepad_state.command_palette.is_none()
+                && ctx.input(|i| i.key_pressed(egui::Key::Escape))
+            {
+                close_find(state);
             }
 
-            if ctx.input(|i| i.key_pressed(egui::Key::S) && modifier) {
-                on_save_button_clicked(state);
+            // Ctrl+wheel (and pinch) arrive as a zoom factor instead of a scroll.
+            let zoom_delta = ctx.input(|i| i.zoom_delta());
+            if zoom_delta != 1.0 {
+                set_zoom(state, state.notepad_state.zoom * zoom_delta);
             }
-
-            if ctx.input(|i| i.key_pressed(egui::Key::Comma) && modifier) {
-                state.screen = Screen::Settings;
+        }
+        Screen::Settings => {
+            if state.keybindings_state.recording.is_some() {
+                capture_recording(ctx, &mut state.keybindings_state);
+            } else if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+                discard_changes(state);
+                state.screen = Screen::Notepad;
+            }
+        }
+        Screen::FindInFiles => {
+            if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+                state.screen = Screen::Notepad;
             }
         }
-        Screen::Settings => {}
     }
 }
 
-fn get_modifier(ctx: &egui::Context) -> bool {
-    ctx.input(|i| {
-        if cfg!(target_os = "macos") {
-            i.modifiers.mac_cmd
-        } else {
-            i.modifiers.ctrl
+/// Runs a bound action through the same handlers as the menus.
+pub fn run_action(state: &mut AppState, action: Action) {
+    match action {
+        Action::NewFile => on_new_button_clicked(state),
+        Action::OpenFile => on_open_button_clicked(state),
+        Action::Save => on_save_button_clicked(state),
+        Action::SaveAs => on_save_as_button_clicked(state),
+        Action::SaveCopyAs => on_save_copy_button_clicked(state),
+        Action::RevertToSaved => on_revert_button_clicked(state),
+        Action::ReopenWithEncoding => on_reopen_with_encoding_clicked(state),
+        Action::SaveWithEncoding => on_save_with_encoding_clicked(state),
+        Action::ExportPdf => on_export_pdf_clicked(state),
+        Action::ExportHtml => on_export_html_clicked(state),
+        Action::Print => on_print_clicked(state),
+        Action::CloseTab => on_close_tab_clicked(state, state.notepad_state.active_document),
+        Action::Undo => on_undo_button_clicked(state),
+        Action::Redo => on_redo_button_clicked(state),
+        Action::CopyAsHtml => on_copy_as_html_clicked(state),
+        Action::Find => on_find_button_clicked(state),
+        Action::Replace => on_replace_button_clicked(state),
+        Action::FindInFiles => on_find_in_files_button_clicked(state),
+        Action::GoToLine => on_go_to_line_button_clicked(state),
+        Action::LineEndingsLf => convert_line_endings(state, LineEnding::Lf),
+        Action::LineEndingsCrLf => convert_line_endings(state, LineEnding::CrLf),
+        Action::LineEndingsCr => convert_line_endings(state, LineEnding::Cr),
+        Action::ToggleLineNumbers => {
+            let show_line_numbers = !state.settings_state.current.show_line_numbers;
+            on_line_numbers_toggled(state, show_line_numbers);
         }
-    })
-}
\ No newline at end of file
+        Action::WordWrapOff => select_word_wrap(state, WordWrap::Off),
+        Action::WordWrapWindow => select_word_wrap(state, WordWrap::Window),
+        Action::WordWrapColumn => select_word_wrap(state, WordWrap::Column),
+        Action::MarkdownPreview => {
+            let show_preview = !state.notepad_state.active().show_preview;
+            on_markdown_preview_toggled(state, show_preview);
+        }
+        Action::ZoomIn => on_zoom_in_clicked(state),
+        Action::ZoomOut => on_zoom_out_clicked(state),
+        Action::ZoomReset => on_zoom_reset_clicked(state),
+        Action::CommandPalette => on_command_palette_clicked(state),
+        Action::OpenSettings => state.screen = Screen::Settings,
+    }
+}
+
+fn select_word_wrap(state: &mut AppState, word_wrap: WordWrap) {
+    let wrap_column = state.settings_state.current.wrap_column;
+    on_word_wrap_selected(state, word_wrap, wrap_column);
+}
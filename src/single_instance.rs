@@ -0,0 +1,313 @@
+use crate::cli::CliArgs;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, DirBuilder};
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+
+/// Bumped whenever `Request` or `Response` change in an incompatible way.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Sent by a second invocation to the running instance, one JSON object per line.
+#[derive(Serialize, Deserialize)]
+pub struct Request {
+    pub version: u32,
+    pub args: CliArgs,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Response {
+    pub version: u32,
+    pub accepted: bool,
+    pub error: Option<String>,
+}
+
+/// The socket lives in a directory only the current user can enter, so other users can
+/// neither connect to it nor put their own listener in its place.
+pub fn socket_path() -> io::Result<PathBuf> {
+    // SAFETY: `geteuid` has no preconditions and cannot fail.
+    let uid = unsafe { libc::geteuid() };
+    let dir = match dirs::runtime_dir() {
+        Some(dir) => dir,
+        None => std::env::temp_dir().join(format!("notepadr-{}", uid)),
+    };
+    private_dir(&dir, uid)?;
+    Ok(dir.join("notepadr.sock"))
+}
+
+/// Creates `dir` with mode 0700 if it is missing, and checks that an existing one is a real
+/// directory owned by `uid` that nobody else can access.
+fn private_dir(dir: &Path, uid: u32) -> io::Result<()> {
+    match DirBuilder::new().mode(0o700).create(dir) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(e),
+    }
+
+    let metadata = fs::symlink_metadata(dir)?;
+    if !metadata.is_dir() || metadata.uid() != uid || metadata.mode() & 0o077 != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{:?} is not a private directory", dir),
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn write_message<T: Serialize>(stream: &mut impl Write, message: &T) -> io::Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+    stream.flush()
+}
+
+pub fn read_message<T: for<'de> Deserialize<'de>>(stream: &mut impl BufRead) -> io::Result<T> {
+    let mut line = String::new();
+    if stream.read_line(&mut line)? == 0 {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(serde_json::from_str(&line)?)
+}
+
+/// Answers a single request, passing the arguments on when the version matches.
+pub fn handle_connection(
+    reader: &mut impl BufRead,
+    writer: &mut impl Write,
+    sender: &Sender<CliArgs>,
+) -> io::Result<()> {
+    let request: Request = read_message(reader)?;
+
+    let response = if request.version == PROTOCOL_VERSION {
+        let accepted = sender.send(request.args).is_ok();
+        Response {
+            version: PROTOCOL_VERSION,
+            accepted,
+            error: (!accepted).then(|| "instance is shutting down".to_string()),
+        }
+    } else {
+        Response {
+            version: PROTOCOL_VERSION,
+            accepted: false,
+            error: Some(format!("unsupported protocol version {}", request.version)),
+        }
+    };
+
+    write_message(writer, &response)
+}
+
+/// Hands the arguments to an instance already listening on `path`.
+///
+/// Returns `Ok(false)` when nobody is listening or the instance refused the request,
+/// in which case the caller should start its own window.
+pub fn forward_to_running_instance(path: &Path, args: CliArgs) -> io::Result<bool> {
+    let mut stream = match UnixStream::connect(path) {
+        Ok(stream) => stream,
+        Err(e) if matches!(e.kind(), io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused) => {
+            return Ok(false);
+        }
+        Err(e) => return Err(e),
+    };
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    write_message(&mut stream, &Request { version: PROTOCOL_VERSION, args })?;
+    let response: Response = read_message(&mut BufReader::new(&stream))?;
+
+    if !response.accepted {
+        warn!(
+            "Running instance (protocol {}) refused the files: {}",
+            response.version,
+            response.error.unwrap_or_default()
+        );
+    }
+
+    Ok(response.accepted)
+}
+
+/// Listens for other invocations and queues the files they forward.
+pub struct InstanceServer {
+    path: PathBuf,
+    receiver: Receiver<CliArgs>,
+}
+
+impl InstanceServer {
+    /// Binds the socket, replacing one left behind by an instance that crashed.
+    /// `on_request` runs on the listener thread after each request is queued.
+    pub fn start(path: PathBuf, on_request: impl Fn() + Send + 'static) -> io::Result<Self> {
+        let listener = match UnixListener::bind(&path) {
+            Ok(listener) => listener,
+            Err(e) if e.kind() == io::ErrorKind::AddrInUse && UnixStream::connect(&path).is_err() => {
+                fs::remove_file(&path)?;
+                UnixListener::bind(&path)?
+            }
+            Err(e) => return Err(e),
+        };
+        info!("Listening for other instances on {:?}", path);
+
+        let (sender, receiver) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let result = stream.and_then(|stream| {
+                    stream.set_read_timeout(Some(TIMEOUT))?;
+                    stream.set_write_timeout(Some(TIMEOUT))?;
+                    handle_connection(&mut BufReader::new(&stream), &mut &stream, &sender)
+                });
+
+                match result {
+                    Ok(()) => on_request(),
+                    Err(e) => error!("ERROR: handling instance request -> {}", e),
+                }
+            }
+        });
+
+        Ok(Self { path, receiver })
+    }
+
+    pub fn try_recv(&self) -> Option<CliArgs> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl Drop for InstanceServer {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::FileArg;
+    use std::io::Cursor;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn args() -> CliArgs {
+        CliArgs {
+            files: vec![FileArg {
+                path: PathBuf::from("notes.txt"),
+                line: Some(3),
+                column: Some(7),
+            }],
+            readonly: true,
+            ..Default::default()
+        }
+    }
+
+    fn request_line(request: &Request) -> Vec<u8> {
+        let mut line = Vec::new();
+        write_message(&mut line, request).unwrap();
+        line
+    }
+
+    #[test]
+    fn matching_version_forwards_the_files() {
+        let (sender, receiver) = mpsc::channel();
+        let input = request_line(&Request { version: PROTOCOL_VERSION, args: args() });
+        let mut output = Vec::new();
+
+        handle_connection(&mut Cursor::new(input), &mut output, &sender).unwrap();
+
+        let response: Response = read_message(&mut Cursor::new(output)).unwrap();
+        assert!(response.accepted);
+        assert!(response.error.is_none());
+        let forwarded = receiver.try_recv().unwrap();
+        assert_eq!(forwarded.files.len(), 1);
+        assert_eq!(forwarded.files[0].path, PathBuf::from("notes.txt"));
+        assert_eq!((forwarded.files[0].line, forwarded.files[0].column), (Some(3), Some(7)));
+        assert!(forwarded.readonly);
+    }
+
+    #[test]
+    fn other_versions_are_refused() {
+        let (sender, receiver) = mpsc::channel();
+        let input = request_line(&Request { version: PROTOCOL_VERSION + 1, args: args() });
+        let mut output = Vec::new();
+
+        handle_connection(&mut Cursor::new(input), &mut output, &sender).unwrap();
+
+        let response: Response = read_message(&mut Cursor::new(output)).unwrap();
+        assert!(!response.accepted);
+        assert_eq!(response.version, PROTOCOL_VERSION);
+        assert!(response.error.unwrap().contains("version"));
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn requests_are_refused_while_shutting_down() {
+        let (sender, receiver) = mpsc::channel();
+        drop(receiver);
+        let input = request_line(&Request { version: PROTOCOL_VERSION, args: args() });
+        let mut output = Vec::new();
+
+        handle_connection(&mut Cursor::new(input), &mut output, &sender).unwrap();
+
+        let response: Response = read_message(&mut Cursor::new(output)).unwrap();
+        assert!(!response.accepted);
+    }
+
+    #[test]
+    fn malformed_input_is_an_error_without_a_reply() {
+        let (sender, receiver) = mpsc::channel();
+        for input in [&b"not json\n"[..], b"{\"version\": 1}\n", b""] {
+            let mut output = Vec::new();
+            assert!(handle_connection(&mut Cursor::new(input), &mut output, &sender).is_err());
+            assert!(output.is_empty());
+        }
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn round_trip_over_a_socket_pair() {
+        let (client, server) = UnixStream::pair().unwrap();
+        let (sender, receiver) = mpsc::channel();
+        let handle = std::thread::spawn(move || {
+            handle_connection(&mut BufReader::new(&server), &mut &server, &sender)
+        });
+
+        let mut client_writer = &client;
+        write_message(&mut client_writer, &Request { version: PROTOCOL_VERSION, args: args() }).unwrap();
+        let response: Response = read_message(&mut BufReader::new(&client)).unwrap();
+
+        handle.join().unwrap().unwrap();
+        assert!(response.accepted);
+        assert_eq!(receiver.try_recv().unwrap().files[0].path, PathBuf::from("notes.txt"));
+    }
+
+    #[test]
+    fn forwards_to_a_listening_instance() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("instance.sock");
+        assert!(!forward_to_running_instance(&path, args()).unwrap());
+
+        let server = InstanceServer::start(path.clone(), || {}).unwrap();
+        assert!(forward_to_running_instance(&path, args()).unwrap());
+
+        let forwarded = server.receiver.recv_timeout(TIMEOUT).unwrap();
+        assert_eq!(forwarded.files[0].path, PathBuf::from("notes.txt"));
+        drop(server);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn the_socket_directory_must_be_private() {
+        let temp = tempfile::tempdir().unwrap();
+        let uid = fs::metadata(temp.path()).unwrap().uid();
+
+        let dir = temp.path().join("new");
+        private_dir(&dir, uid).unwrap();
+        assert_eq!(fs::metadata(&dir).unwrap().mode() & 0o777, 0o700);
+        private_dir(&dir, uid).unwrap();
+
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(private_dir(&dir, uid).is_err());
+        assert!(private_dir(temp.path(), uid + 1).is_err());
+    }
+}